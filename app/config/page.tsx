@@ -2,22 +2,35 @@
 
 import { useState, useEffect, useRef } from "react";
 import { motion, AnimatePresence } from "framer-motion";
-import { Save, Eye, EyeOff, Key, Keyboard, Brain, AlertCircle, CheckCircle, Mic2, ArrowUpToLine, Globe } from "lucide-react";
-import { useTauri } from "@/hooks/useTauri";
+import { Save, Eye, EyeOff, Key, Keyboard, Brain, AlertCircle, CheckCircle, Mic2, ArrowUpToLine, Globe, Server } from "lucide-react";
+import { useTauri, MicTest } from "@/hooks/useTauri";
 import { useLanguage } from "@/contexts/LanguageContext";
 import { Lang } from "@/lib/translations";
 
 export default function ConfigPage() {
-    const { getConfig, saveConfig, isTauri } = useTauri();
+    const { getConfig, saveConfig, isTauri, getInputDevices, getUploadFormats, testMicrophone } = useTauri();
     const { t, lang, setLang } = useLanguage();
 
     const [apiKey, setApiKey] = useState("");
     const [hotkey, setHotkey] = useState("Alt+Space");
     const [systemPrompt, setSystemPrompt] = useState("");
     const [sttModel, setSttModel] = useState("whisper-large-v3");
+    const [uploadFormat, setUploadFormat] = useState("flac");
+    const [uploadFormats, setUploadFormats] = useState<string[]>(["flac", "wav"]);
     const [llmModel, setLlmModel] = useState("llama-3.3-70b-versatile");
     const [overlayPosition, setOverlayPosition] = useState<"top" | "bottom">("top");
+    const [continuous, setContinuous] = useState(false);
+    const [preprocess, setPreprocess] = useState({ high_pass: false, noise_suppression: false, agc: false });
+    const [prerollMs, setPrerollMs] = useState(0);
+    const [inputDevices, setInputDevices] = useState<string[]>([]);
+    const [testDevice, setTestDevice] = useState("");
+    const [testPlayback, setTestPlayback] = useState(false);
+    const [micTest, setMicTest] = useState<"idle" | "running" | "error" | MicTest>("idle");
     const [showKey, setShowKey] = useState(false);
+    const [apiEnabled, setApiEnabled] = useState(false);
+    const [apiPort, setApiPort] = useState(47110);
+    const [apiToken, setApiToken] = useState("");
+    const [showToken, setShowToken] = useState(false);
     const [saved, setSaved] = useState<"idle" | "saving" | "done" | "error">("idle");
     const [isCapturing, setIsCapturing] = useState(false);
     const hotkeyInputRef = useRef<HTMLInputElement>(null);
@@ -30,9 +43,35 @@ export default function ConfigPage() {
             setHotkey(cfg.hotkey || "Alt+Space");
             setSystemPrompt(cfg.system_prompt || "");
             setSttModel(cfg.stt_model || "whisper-large-v3");
+            setUploadFormat(cfg.upload_format || "flac");
             setLlmModel(cfg.llm_model || "llama-3.3-70b-versatile");
             setOverlayPosition(cfg.overlay_position === "bottom" ? "bottom" : "top");
+            setContinuous(!!cfg.continuous);
+            if (cfg.preprocess) setPreprocess(cfg.preprocess);
+            setPrerollMs(cfg.preroll_ms || 0);
+            setApiEnabled(!!cfg.api_enabled);
+            setApiPort(cfg.api_port || 47110);
+            setApiToken(cfg.api_token || "");
         });
+        getInputDevices().then((d) => setInputDevices(d.devices));
+        getUploadFormats().then(setUploadFormats);
+        // eslint-disable-next-line react-hooks/exhaustive-deps
+    }, [isTauri]);
+
+    // The mode can also be switched over the control socket (`set-mode`);
+    // follow it so saving this page doesn't switch it back
+    useEffect(() => {
+        if (!isTauri) return;
+        let unlisten: (() => void) | null = null;
+        const setup = async () => {
+            const { listen } = await import("@tauri-apps/api/event");
+            unlisten = await listen("config-changed", async () => {
+                const cfg: any = await getConfig();
+                if (cfg) setContinuous(!!cfg.continuous);
+            });
+        };
+        setup();
+        return () => { unlisten?.(); };
         // eslint-disable-next-line react-hooks/exhaustive-deps
     }, [isTauri]);
 
@@ -45,10 +84,20 @@ export default function ConfigPage() {
                 groq_api_key: apiKey,
                 hotkey,
                 stt_model: sttModel,
+                upload_format: uploadFormat,
                 llm_model: llmModel,
                 overlay_position: overlayPosition,
+                continuous,
+                preprocess: { ...cfg?.preprocess, ...preprocess },
+                preroll_ms: prerollMs,
                 system_prompt: systemPrompt,
+                api_enabled: apiEnabled,
+                api_port: apiPort,
+                api_token: apiToken,
             });
+            // Enabling the API without a token creates one
+            const updated: any = await getConfig();
+            setApiToken(updated?.api_token || "");
             setSaved("done");
             setTimeout(() => setSaved("idle"), 3000);
         } catch {
@@ -57,6 +106,16 @@ export default function ConfigPage() {
         }
     };
 
+    const runMicTest = async () => {
+        setMicTest("running");
+        try {
+            const result = await testMicrophone(testDevice || null, testPlayback);
+            setMicTest(result ?? "error");
+        } catch {
+            setMicTest("error");
+        }
+    };
+
     return (
         <div style={{ display: "flex", flexDirection: "column", gap: "28px" }}>
             <div className="page-header">
@@ -337,6 +396,191 @@ export default function ConfigPage() {
                 </div>
             </section>
 
+            {/* Dictation Mode */}
+            <section>
+                <div style={{ display: "flex", alignItems: "center", gap: "8px", marginBottom: "16px" }}>
+                    <Mic2 size={15} style={{ color: "var(--accent)" }} />
+                    <h2 style={{ fontSize: "15px", fontWeight: "600", color: "var(--text-primary)", fontFamily: "var(--font-ui)" }}>
+                        {t("modeSection")}
+                    </h2>
+                </div>
+                <div className="card">
+                    <label className="section-label" style={{ display: "block", marginBottom: "12px" }}>
+                        {t("modeLabel")}
+                    </label>
+                    <div style={{ display: "flex", gap: "12px" }}>
+                        {([false, true] as const).map((mode) => (
+                            <button
+                                key={String(mode)}
+                                onClick={() => setContinuous(mode)}
+                                style={{
+                                    flex: 1,
+                                    maxWidth: "160px",
+                                    padding: "10px 16px",
+                                    borderRadius: "8px",
+                                    border: continuous === mode
+                                        ? "1px solid var(--accent)"
+                                        : "1px solid var(--border)",
+                                    background: continuous === mode
+                                        ? "rgba(124,58,237,0.15)"
+                                        : "rgba(0,0,0,0.2)",
+                                    color: continuous === mode ? "var(--accent)" : "var(--text-muted)",
+                                    cursor: "pointer",
+                                    fontSize: "13px",
+                                    fontFamily: "var(--font-ui)",
+                                    fontWeight: continuous === mode ? 600 : 400,
+                                    transition: "all 0.2s",
+                                    display: "flex",
+                                    alignItems: "center",
+                                    justifyContent: "center",
+                                    gap: "6px",
+                                }}
+                            >
+                                {mode ? t("modeContinuous") : t("modePushToTalk")}
+                            </button>
+                        ))}
+                    </div>
+                    <p style={{ fontSize: "11px", color: "var(--text-muted)", marginTop: "10px", fontFamily: "var(--font-ui)" }}>
+                        {continuous ? t("modeContinuousHint") : t("modePushToTalkHint")}
+                    </p>
+                </div>
+            </section>
+
+            {/* Audio Processing */}
+            <section>
+                <div style={{ display: "flex", alignItems: "center", gap: "8px", marginBottom: "16px" }}>
+                    <Mic2 size={15} style={{ color: "var(--accent)" }} />
+                    <h2 style={{ fontSize: "15px", fontWeight: "600", color: "var(--text-primary)", fontFamily: "var(--font-ui)" }}>
+                        {t("processingSection")}
+                    </h2>
+                </div>
+                <div className="card" style={{ display: "flex", flexDirection: "column", gap: "12px" }}>
+                    {([
+                        ["high_pass", "processingHighPass", "processingHighPassHint"],
+                        ["noise_suppression", "processingNoise", "processingNoiseHint"],
+                        ["agc", "processingAgc", "processingAgcHint"],
+                    ] as const).map(([key, label, hint]) => (
+                        <div key={key} style={{ display: "flex", alignItems: "center", justifyContent: "space-between", gap: "16px" }}>
+                            <div>
+                                <label className="section-label" style={{ display: "block" }}>{t(label)}</label>
+                                <p style={{ fontSize: "11px", color: "var(--text-muted)", marginTop: "4px", fontFamily: "var(--font-ui)" }}>
+                                    {t(hint)}
+                                </p>
+                            </div>
+                            <button
+                                onClick={() => setPreprocess((p) => ({ ...p, [key]: !p[key] }))}
+                                style={{
+                                    minWidth: "64px",
+                                    padding: "6px 12px",
+                                    borderRadius: "8px",
+                                    border: preprocess[key]
+                                        ? "1px solid var(--accent)"
+                                        : "1px solid var(--border)",
+                                    background: preprocess[key]
+                                        ? "rgba(124,58,237,0.15)"
+                                        : "rgba(0,0,0,0.2)",
+                                    color: preprocess[key] ? "var(--accent)" : "var(--text-muted)",
+                                    cursor: "pointer",
+                                    fontSize: "13px",
+                                    fontFamily: "var(--font-ui)",
+                                    fontWeight: preprocess[key] ? 600 : 400,
+                                    transition: "all 0.2s",
+                                }}
+                            >
+                                {preprocess[key] ? t("toggleOn") : t("toggleOff")}
+                            </button>
+                        </div>
+                    ))}
+                    <div style={{ display: "flex", alignItems: "center", justifyContent: "space-between", gap: "16px" }}>
+                        <div>
+                            <label className="section-label" style={{ display: "block" }}>{t("processingPreroll")}</label>
+                            <p style={{ fontSize: "11px", color: "var(--text-muted)", marginTop: "4px", fontFamily: "var(--font-ui)" }}>
+                                {t("processingPrerollHint")}
+                            </p>
+                        </div>
+                        <button
+                            onClick={() => setPrerollMs((ms) => (ms > 0 ? 0 : 500))}
+                            style={{
+                                minWidth: "64px",
+                                padding: "6px 12px",
+                                borderRadius: "8px",
+                                border: prerollMs > 0
+                                    ? "1px solid var(--accent)"
+                                    : "1px solid var(--border)",
+                                background: prerollMs > 0
+                                    ? "rgba(124,58,237,0.15)"
+                                    : "rgba(0,0,0,0.2)",
+                                color: prerollMs > 0 ? "var(--accent)" : "var(--text-muted)",
+                                cursor: "pointer",
+                                fontSize: "13px",
+                                fontFamily: "var(--font-ui)",
+                                fontWeight: prerollMs > 0 ? 600 : 400,
+                                transition: "all 0.2s",
+                            }}
+                        >
+                            {prerollMs > 0 ? t("toggleOn") : t("toggleOff")}
+                        </button>
+                    </div>
+                    <div>
+                        <label className="section-label" style={{ display: "block" }}>{t("micTest")}</label>
+                        <p style={{ fontSize: "11px", color: "var(--text-muted)", marginTop: "4px", marginBottom: "8px", fontFamily: "var(--font-ui)" }}>
+                            {t("micTestHint")}
+                        </p>
+                        <div style={{ display: "flex", alignItems: "center", gap: "10px" }}>
+                            <select value={testDevice} onChange={(e) => setTestDevice(e.target.value)} style={{ flex: 1 }}>
+                                <option value="">{t("micTestDefaultDevice")}</option>
+                                {inputDevices.map((d) => (
+                                    <option key={d} value={d}>{d}</option>
+                                ))}
+                            </select>
+                            <label style={{ display: "flex", alignItems: "center", gap: "6px", fontSize: "12px", color: "var(--text-secondary)", fontFamily: "var(--font-ui)", whiteSpace: "nowrap" }}>
+                                <input type="checkbox" checked={testPlayback} onChange={(e) => setTestPlayback(e.target.checked)} />
+                                {t("micTestPlayback")}
+                            </label>
+                            <button
+                                onClick={runMicTest}
+                                disabled={micTest === "running"}
+                                style={{
+                                    minWidth: "64px",
+                                    padding: "6px 12px",
+                                    borderRadius: "8px",
+                                    border: "1px solid var(--accent)",
+                                    background: "rgba(124,58,237,0.15)",
+                                    color: "var(--accent)",
+                                    cursor: micTest === "running" ? "wait" : "pointer",
+                                    fontSize: "13px",
+                                    fontFamily: "var(--font-ui)",
+                                    fontWeight: 600,
+                                    whiteSpace: "nowrap",
+                                }}
+                            >
+                                {micTest === "running" ? t("micTestRunning") : t("micTestRun")}
+                            </button>
+                        </div>
+                        {typeof micTest === "object" && micTest.suggested_threshold !== null && (
+                            <p style={{ fontSize: "12px", color: "var(--text-secondary)", marginTop: "8px", fontFamily: "var(--font-ui)", direction: "ltr" }}>
+                                {t("micTestResult")
+                                    .replace("{speech}", micTest.quality.speech_dbfs.toFixed(1))
+                                    .replace("{floor}", micTest.quality.noise_floor_dbfs.toFixed(1))
+                                    .replace("{threshold}", micTest.suggested_threshold.toFixed(4))}
+                            </p>
+                        )}
+                        {typeof micTest === "object" && micTest.suggested_threshold === null && (
+                            <p style={{ fontSize: "12px", color: "#f59e0b", marginTop: "8px", fontFamily: "var(--font-ui)" }}>
+                                {t("micTestNoSpeech")
+                                    .replace("{floor}", micTest.quality.noise_floor_dbfs.toFixed(1))
+                                    .replace("{threshold}", micTest.previous_threshold.toFixed(4))}
+                            </p>
+                        )}
+                        {micTest === "error" && (
+                            <p style={{ fontSize: "12px", color: "var(--danger)", marginTop: "8px", fontFamily: "var(--font-ui)" }}>
+                                {t("micTestFailed")}
+                            </p>
+                        )}
+                    </div>
+                </div>
+            </section>
+
             {/* AI Models Section */}
             <section>
                 <div style={{ display: "flex", alignItems: "center", gap: "8px", marginBottom: "16px" }}>
@@ -364,6 +608,24 @@ export default function ConfigPage() {
                         </p>
                     </div>
 
+                    {/* Upload Format */}
+                    <div>
+                        <div style={{ display: "flex", alignItems: "center", gap: "7px", marginBottom: "8px" }}>
+                            <Mic2 size={13} style={{ color: "var(--text-muted)" }} />
+                            <label className="section-label" style={{ display: "block" }}>
+                                {t("uploadFormatLabel")}
+                            </label>
+                        </div>
+                        <select value={uploadFormat} onChange={(e) => setUploadFormat(e.target.value)}>
+                            <option value="flac">{t("uploadFormatFlac")}</option>
+                            {uploadFormats.includes("opus") && <option value="opus">{t("uploadFormatOpus")}</option>}
+                            <option value="wav">{t("uploadFormatWav")}</option>
+                        </select>
+                        <p style={{ fontSize: "11px", color: "var(--text-muted)", marginTop: "6px", fontFamily: "var(--font-ui)" }}>
+                            {t("uploadFormatHint")}
+                        </p>
+                    </div>
+
                     {/* LLM Model */}
                     <div>
                         <div style={{ display: "flex", alignItems: "center", gap: "7px", marginBottom: "8px" }}>
@@ -443,6 +705,105 @@ export default function ConfigPage() {
                     </div>
                 </div>
             </section>
+
+            {/* Local API */}
+            <section>
+                <div style={{ display: "flex", alignItems: "center", gap: "8px", marginBottom: "16px" }}>
+                    <Server size={15} style={{ color: "var(--accent)" }} />
+                    <h2 style={{ fontSize: "15px", fontWeight: "600", color: "var(--text-primary)", fontFamily: "var(--font-ui)" }}>
+                        {t("localApiSection")}
+                    </h2>
+                </div>
+                <div className="card" style={{ display: "flex", flexDirection: "column", gap: "16px" }}>
+                    <div style={{ display: "flex", alignItems: "center", justifyContent: "space-between", gap: "16px" }}>
+                        <div>
+                            <label className="section-label" style={{ display: "block" }}>{t("localApiEnable")}</label>
+                            <p style={{ fontSize: "11px", color: "var(--text-muted)", marginTop: "4px", fontFamily: "var(--font-ui)" }}>
+                                {t("localApiHint")}
+                            </p>
+                        </div>
+                        <button
+                            onClick={() => setApiEnabled((on) => !on)}
+                            style={{
+                                minWidth: "64px",
+                                padding: "6px 12px",
+                                borderRadius: "8px",
+                                border: apiEnabled
+                                    ? "1px solid var(--accent)"
+                                    : "1px solid var(--border)",
+                                background: apiEnabled
+                                    ? "rgba(124,58,237,0.15)"
+                                    : "rgba(0,0,0,0.2)",
+                                color: apiEnabled ? "var(--accent)" : "var(--text-muted)",
+                                cursor: "pointer",
+                                fontSize: "13px",
+                                fontFamily: "var(--font-ui)",
+                                fontWeight: apiEnabled ? 600 : 400,
+                                transition: "all 0.2s",
+                            }}
+                        >
+                            {apiEnabled ? t("toggleOn") : t("toggleOff")}
+                        </button>
+                    </div>
+                    {apiEnabled && (
+                        <div style={{ display: "flex", gap: "12px", alignItems: "flex-end" }}>
+                            <div style={{ width: "110px" }}>
+                                <label className="section-label" style={{ display: "block", marginBottom: "8px" }}>
+                                    {t("localApiPort")}
+                                </label>
+                                <input
+                                    type="number"
+                                    min={1024}
+                                    max={65535}
+                                    value={apiPort}
+                                    onChange={(e) => setApiPort(Number(e.target.value) || 47110)}
+                                    style={{ fontFamily: "'JetBrains Mono', monospace", fontSize: "12px", direction: "ltr" }}
+                                />
+                            </div>
+                            <div style={{ flex: 1 }}>
+                                <label className="section-label" style={{ display: "block", marginBottom: "8px" }}>
+                                    {t("localApiToken")}
+                                </label>
+                                <div style={{ position: "relative" }}>
+                                    <input
+                                        type={showToken ? "text" : "password"}
+                                        value={apiToken}
+                                        readOnly
+                                        placeholder={t("localApiTokenPending")}
+                                        onFocus={(e) => e.target.select()}
+                                        style={{
+                                            paddingInlineStart: "40px",
+                                            fontFamily: "'JetBrains Mono', monospace",
+                                            fontSize: "12px",
+                                            direction: "ltr",
+                                        }}
+                                    />
+                                    <button
+                                        onClick={() => setShowToken(!showToken)}
+                                        style={{
+                                            position: "absolute",
+                                            insetInlineStart: "12px",
+                                            top: "50%",
+                                            transform: "translateY(-50%)",
+                                            background: "none",
+                                            border: "none",
+                                            cursor: "pointer",
+                                            color: "var(--text-muted)",
+                                            display: "flex",
+                                            alignItems: "center",
+                                        }}
+                                    >
+                                        {showToken ? <EyeOff size={14} /> : <Eye size={14} />}
+                                    </button>
+                                </div>
+                            </div>
+                            <button className="btn-ghost" onClick={() => setApiToken("")} disabled={!apiToken}>
+                                {t("localApiNewToken")}
+                            </button>
+                        </div>
+                    )}
+                </div>
+            </section>
         </div>
     );
 }
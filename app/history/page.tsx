@@ -1,9 +1,10 @@
 "use client";
 
-import { useState, useEffect } from "react";
+import { useState, useEffect, useRef } from "react";
 import { motion, AnimatePresence } from "framer-motion";
-import { Trash2, Copy, Search, Clock, FileText, CheckCircle, Trash } from "lucide-react";
-import { useTauri } from "@/hooks/useTauri";
+import { Trash2, Copy, Search, Clock, FileText, CheckCircle, Trash, FileAudio, AlertCircle, RotateCcw, Settings } from "lucide-react";
+import { useRouter } from "next/navigation";
+import { useTauri, PipelineError, SubtitleFormat, ErrorAction, errorAction } from "@/hooks/useTauri";
 import { useLanguage } from "@/contexts/LanguageContext";
 
 interface HistoryEntry {
@@ -12,15 +13,26 @@ interface HistoryEntry {
     raw: string;
     refined: string;
     word_count: number;
+    source?: string;
 }
 
 export default function HistoryPage() {
-    const { getHistory, clearHistory, deleteHistoryEntry, isTauri } = useTauri();
+    const { getHistory, clearHistory, deleteHistoryEntry, transcribeFile, translateError, isTauri } = useTauri();
     const { t, lang } = useLanguage();
+    const router = useRouter();
 
     const [history, setHistory] = useState<HistoryEntry[]>([]);
     const [search, setSearch] = useState("");
     const [copiedId, setCopiedId] = useState<string | null>(null);
+    const [dragging, setDragging] = useState(false);
+    const [pendingFile, setPendingFile] = useState<string | null>(null);
+    // `path` is the file a Retry transcribes again
+    const [fileError, setFileError] = useState<{ message: string; action: ErrorAction | null; path: string } | null>(null);
+    const [subtitleFormat, setSubtitleFormat] = useState<SubtitleFormat | "">("");
+    const [subtitleSaved, setSubtitleSaved] = useState<string | null>(null);
+    // The drop listener is registered once; read the current choice through a ref
+    const subtitleFormatRef = useRef(subtitleFormat);
+    subtitleFormatRef.current = subtitleFormat;
 
     const refresh = async () => {
         const h = await getHistory();
@@ -30,6 +42,51 @@ export default function HistoryPage() {
     // eslint-disable-next-line react-hooks/exhaustive-deps
     useEffect(() => { if (isTauri) refresh(); }, [isTauri]);
 
+    const transcribeDropped = async (path: string) => {
+        setPendingFile(path.split(/[\\/]/).pop() || path);
+        try {
+            const result = await transcribeFile(path, subtitleFormatRef.current || null);
+            if (result?.subtitle_path) setSubtitleSaved(result.subtitle_path);
+            await refresh();
+        } catch (err) {
+            const e = err as PipelineError | string;
+            setFileError({ message: translateError(e), action: errorAction(e), path });
+        }
+    };
+
+    const retryFile = async (path: string) => {
+        setFileError(null);
+        setSubtitleSaved(null);
+        await transcribeDropped(path);
+        setPendingFile(null);
+    };
+
+    // Dropped files are transcribed one at a time, each landing in history
+    useEffect(() => {
+        if (!isTauri) return;
+        let unlisten: (() => void) | null = null;
+        const setup = async () => {
+            const { getCurrentWebview } = await import("@tauri-apps/api/webview");
+            unlisten = await getCurrentWebview().onDragDropEvent(async (e) => {
+                if (e.payload.type === "enter" || e.payload.type === "over") {
+                    setDragging(true);
+                    return;
+                }
+                setDragging(false);
+                if (e.payload.type !== "drop") return;
+                setFileError(null);
+                setSubtitleSaved(null);
+                for (const path of e.payload.paths) {
+                    await transcribeDropped(path);
+                }
+                setPendingFile(null);
+            });
+        };
+        setup();
+        return () => { unlisten?.(); };
+        // eslint-disable-next-line react-hooks/exhaustive-deps
+    }, [isTauri]);
+
     const filtered = history.filter(
         (e) =>
             e.refined?.toLowerCase().includes(search.toLowerCase()) ||
@@ -89,6 +146,63 @@ export default function HistoryPage() {
                 )}
             </div>
 
+            {/* Drop target for audio/video files */}
+            <div
+                style={{
+                    display: "flex",
+                    alignItems: "center",
+                    gap: "10px",
+                    padding: "14px 16px",
+                    border: dragging ? "1px dashed var(--accent)" : "1px dashed var(--border)",
+                    background: dragging ? "rgba(124,58,237,0.12)" : "transparent",
+                    borderRadius: "var(--radius-md)",
+                    fontSize: "12px",
+                    color: dragging ? "var(--accent)" : "var(--text-muted)",
+                    fontFamily: "var(--font-ui)",
+                    transition: "all 0.2s",
+                }}
+            >
+                <FileAudio size={15} style={{ flexShrink: 0 }} />
+                <span style={{ flex: 1 }}>
+                    {pendingFile
+                        ? t("fileTranscribing").replace("{name}", pendingFile)
+                        : dragging ? t("dropFilesActive") : t("dropFilesHint")}
+                </span>
+                <label style={{ display: "flex", alignItems: "center", gap: "6px", whiteSpace: "nowrap" }}>
+                    {t("subtitlesLabel")}
+                    <select
+                        value={subtitleFormat}
+                        onChange={(e) => setSubtitleFormat(e.target.value as SubtitleFormat | "")}
+                        style={{ width: "auto", padding: "4px 8px", fontSize: "12px" }}
+                    >
+                        <option value="">{t("subtitlesNone")}</option>
+                        <option value="srt">SRT</option>
+                        <option value="vtt">WebVTT</option>
+                    </select>
+                </label>
+            </div>
+            {subtitleSaved && (
+                <div style={{ display: "flex", alignItems: "center", gap: "8px", fontSize: "12px", color: "var(--success)", fontFamily: "var(--font-ui)", direction: "ltr" }}>
+                    <CheckCircle size={13} style={{ flexShrink: 0 }} />
+                    {t("subtitlesSaved").replace("{path}", subtitleSaved)}
+                </div>
+            )}
+            {fileError && (
+                <div style={{ display: "flex", alignItems: "center", gap: "8px", fontSize: "12px", color: "var(--danger)", fontFamily: "var(--font-ui)" }}>
+                    <AlertCircle size={13} style={{ flexShrink: 0 }} />
+                    {fileError.message}
+                    {fileError.action && (
+                        <button
+                            onClick={fileError.action === "retry" ? () => retryFile(fileError.path) : () => router.push("/config")}
+                            style={{ display: "flex", alignItems: "center", gap: "4px", padding: "2px 8px", background: "transparent", border: "1px solid var(--danger)", borderRadius: "var(--radius-md)", color: "var(--danger)", fontSize: "12px", cursor: "pointer", fontFamily: "var(--font-ui)" }}
+                        >
+                            {fileError.action === "retry" ? <RotateCcw size={12} /> : <Settings size={12} />}
+                            {fileError.action === "retry" ? t("errorRetry") : t("errorOpenSettings")}
+                        </button>
+                    )}
+                </div>
+            )}
+
             {/* Search */}
             <div style={{ position: "relative" }}>
                 <input
@@ -137,6 +251,12 @@ export default function HistoryPage() {
                                         {formatDate(entry.timestamp)}
                                     </span>
                                     <span className="badge badge-accent">{entry.word_count} {t("words")}</span>
+                                    {entry.source && (
+                                        <span style={{ display: "flex", alignItems: "center", gap: "4px", fontSize: "11px", color: "var(--text-muted)", fontFamily: "var(--font-ui)", direction: "ltr" }}>
+                                            <FileAudio size={11} />
+                                            {entry.source}
+                                        </span>
+                                    )}
                                 </div>
                                 <div style={{ display: "flex", gap: "6px" }}>
                                     <button
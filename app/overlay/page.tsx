@@ -2,26 +2,38 @@
 
 import { useEffect, useRef, useState } from "react";
 import { motion, AnimatePresence } from "framer-motion";
+import { useLanguage } from "@/contexts/LanguageContext";
+import type { QualityVerdict, RecordingQuality } from "@/hooks/useTauri";
 
 type WidgetStatus =
     | "idle"
     | "recording"
+    | "paused"
     | "transcribing"
     | "refining"
     | "injecting"
     | "done"
+    | "cancelled"
     | "error";
 
 const STATUS_COLOR: Record<WidgetStatus, string> = {
     idle:         "#7c3aed",
     recording:    "#4ade80",
+    paused:       "#f59e0b",
     transcribing: "#fbbf24",
     refining:     "#a78bfa",
     injecting:    "#4ade80",
     done:         "#4ade80",
+    cancelled:    "#94a3b8",
     error:        "#f87171",
 };
 
+const QUALITY_LABEL = {
+    too_quiet:  "qualityTooQuiet",
+    clipping:   "qualityClipping",
+    very_noisy: "qualityVeryNoisy",
+} as const;
+
 // Number of wave bars
 const BAR_COUNT = 24;
 
@@ -40,6 +52,8 @@ const BAR_BELL = Array.from({ length: BAR_COUNT }, (_, i) => {
 export default function OverlayPage() {
     const [status, setStatus] = useState<WidgetStatus>("idle");
     const [visible, setVisible] = useState(false);
+    const [verdict, setVerdict] = useState<QualityVerdict>("ok");
+    const { t } = useLanguage();
     const barsRef = useRef<(HTMLDivElement | null)[]>([]);
 
     useEffect(() => {
@@ -87,6 +101,7 @@ export default function OverlayPage() {
                 }));
 
                 cleanupFns.push(await listen("recording-started", () => {
+                    setVerdict("ok");
                     setStatus("recording");
                     setVisible(true);
                 }));
@@ -95,6 +110,11 @@ export default function OverlayPage() {
                     setStatus("transcribing");
                 }));
 
+                // Mic problem in the recording just stopped — shown in place of the bars
+                cleanupFns.push(await listen<RecordingQuality>("recording-quality", (e) => {
+                    setVerdict(e.payload.verdict);
+                }));
+
                 cleanupFns.push(await listen<string>("processing-status", (e) => {
                     setStatus(e.payload as WidgetStatus);
                 }));
@@ -112,6 +132,7 @@ export default function OverlayPage() {
                 cleanupFns.push(await listen("overlay-hide", async () => {
                     setVisible(false);
                     setStatus("idle");
+                    setVerdict("ok");
                     // Small delay for exit animation to complete, then hide OS window
                     await new Promise(r => setTimeout(r, 350));
                     try { await getCurrentWindow().hide(); } catch { }
@@ -173,6 +194,7 @@ export default function OverlayPage() {
     const color = STATUS_COLOR[status];
     const isRecording  = status === "recording";
     const isProcessing = status === "transcribing" || status === "refining" || status === "injecting";
+    const warning = verdict !== "ok" && !isRecording && status !== "paused" ? t(QUALITY_LABEL[verdict]) : null;
 
     return (
         <div
@@ -220,7 +242,21 @@ export default function OverlayPage() {
                             animation: isProcessing ? "ektb-proc-pulse 1.4s ease-in-out infinite" : "none",
                         }}
                     >
-                        {Array.from({ length: BAR_COUNT }, (_, i) => {
+                        {warning && (
+                            <span
+                                style={{
+                                    alignSelf: "center",
+                                    fontSize: "11px",
+                                    fontWeight: 600,
+                                    color: "#f59e0b",
+                                    whiteSpace: "nowrap",
+                                    fontFamily: "var(--font-ui)",
+                                }}
+                            >
+                                ⚠ {warning}
+                            </span>
+                        )}
+                        {!warning && Array.from({ length: BAR_COUNT }, (_, i) => {
                             const maxH = barMaxHeight(i, BAR_COUNT);
                             return (
                                 <div
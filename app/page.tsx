@@ -13,7 +13,12 @@ import {
   AlertCircle,
   Copy,
   Mic2,
+  Pause,
+  Play,
+  RotateCcw,
+  Settings,
 } from "lucide-react";
+import { useRouter } from "next/navigation";
 import StatsCard from "@/components/StatsCard";
 import ShortcutBadge from "@/components/ShortcutBadge";
 import { useTauri } from "@/hooks/useTauri";
@@ -26,15 +31,23 @@ export default function HomePage() {
     status,
     statusLabel,
     lastTranscription,
+    prerollActive,
+    audioConfig,
+    qualityWarning,
+    recordingLimitNotice,
     error,
+    errorAction,
     startRecording,
     stopAndProcess,
+    pauseRecording,
+    resumeRecording,
     getHistory,
     getConfig,
     getMicName,
   } = useTauri();
 
   const { t, lang } = useLanguage();
+  const router = useRouter();
 
   const [history, setHistory] = useState<
     { id: string; timestamp: string; refined: string; word_count: number }[]
@@ -73,6 +86,20 @@ export default function HomePage() {
     // eslint-disable-next-line react-hooks/exhaustive-deps
   }, [isTauri]);
 
+  // Keep the mic name fresh when a headset is plugged in or removed
+  useEffect(() => {
+    if (!isTauri) return;
+    let unlisten: (() => void) | null = null;
+    const setup = async () => {
+      const { listen } = await import("@tauri-apps/api/event");
+      unlisten = await listen<{ devices: string[]; default: string | null }>("input-devices-changed", (e) => {
+        setMicName(e.payload.default);
+      });
+    };
+    setup();
+    return () => { unlisten?.(); };
+  }, [isTauri]);
+
   // Drive wave bar heights directly via DOM refs to avoid re-renders
   useEffect(() => {
     if (!isTauri) return;
@@ -128,6 +155,8 @@ export default function HomePage() {
     }
   };
 
+  const isPaused = status === "paused";
+
   const displayText = lastTranscription || history[0]?.refined;
 
   return (
@@ -167,6 +196,38 @@ export default function HomePage() {
           }}>
             {micName}
           </span>
+          {audioConfig && (
+            <span
+              title={audioConfig.device}
+              style={{
+                fontSize: "11px",
+                color: "var(--text-muted)",
+                fontFamily: "var(--font-ui)",
+              }}
+            >
+              {t("audioConfigSummary")
+                .replace("{rate}", (audioConfig.sample_rate / 1000).toString())
+                .replace("{channels}", audioConfig.channels.toString())
+                .replace("{format}", audioConfig.sample_format)}
+            </span>
+          )}
+          {prerollActive && !isRecording && (
+            <span
+              title={t("prerollIndicatorHint")}
+              style={{
+                display: "flex",
+                alignItems: "center",
+                gap: "5px",
+                marginInlineStart: "6px",
+                fontSize: "11px",
+                color: "#f59e0b",
+                fontFamily: "var(--font-ui)",
+              }}
+            >
+              <span style={{ width: "6px", height: "6px", borderRadius: "50%", background: "#f59e0b" }} />
+              {t("prerollIndicator")}
+            </span>
+          )}
         </div>
       )}
 
@@ -279,6 +340,75 @@ export default function HomePage() {
           </div>
         )}
 
+        {/* Pause keeps the dictation open; stopping while paused processes what was said */}
+        {isRecording && (
+          <button
+            onClick={isPaused ? resumeRecording : pauseRecording}
+            style={{
+              display: "flex",
+              alignItems: "center",
+              gap: "6px",
+              padding: "6px 14px",
+              background: "var(--bg-card)",
+              border: "1px solid var(--border)",
+              borderRadius: "var(--radius-md)",
+              color: isPaused ? "#f59e0b" : "var(--text-secondary)",
+              fontSize: "12px",
+              cursor: "pointer",
+              fontFamily: "var(--font-ui)",
+            }}
+          >
+            {isPaused ? <Play size={13} /> : <Pause size={13} />}
+            {isPaused ? t("resumeRecording") : t("pauseRecording")}
+          </button>
+        )}
+
+        {/* Mic quality — shown alongside any error, since it's often the cause */}
+        {qualityWarning && !isRecording && (
+          <div
+            style={{
+              display: "flex",
+              alignItems: "center",
+              gap: "8px",
+              padding: "10px 16px",
+              background: "rgba(245,158,11,0.10)",
+              border: "1px solid rgba(245,158,11,0.3)",
+              borderRadius: "var(--radius-md)",
+              color: "#f59e0b",
+              fontSize: "12px",
+              maxWidth: "450px",
+              textAlign: "center",
+              fontFamily: "var(--font-ui)",
+            }}
+          >
+            <AlertCircle size={14} style={{ flexShrink: 0 }} />
+            {qualityWarning}
+          </div>
+        )}
+
+        {/* Stopped by max_recording_secs rather than by the user */}
+        {recordingLimitNotice && !isRecording && (
+          <div
+            style={{
+              display: "flex",
+              alignItems: "center",
+              gap: "8px",
+              padding: "10px 16px",
+              background: "rgba(245,158,11,0.10)",
+              border: "1px solid rgba(245,158,11,0.3)",
+              borderRadius: "var(--radius-md)",
+              color: "#f59e0b",
+              fontSize: "12px",
+              maxWidth: "450px",
+              textAlign: "center",
+              fontFamily: "var(--font-ui)",
+            }}
+          >
+            <Clock size={14} style={{ flexShrink: 0 }} />
+            {recordingLimitNotice}
+          </div>
+        )}
+
         {/* Error */}
         {error && (
           <div
@@ -299,6 +429,28 @@ export default function HomePage() {
           >
             <AlertCircle size={14} style={{ flexShrink: 0 }} />
             {error}
+            {errorAction && (
+              <button
+                onClick={errorAction === "retry" ? startRecording : () => router.push("/config")}
+                style={{
+                  display: "flex",
+                  alignItems: "center",
+                  gap: "4px",
+                  flexShrink: 0,
+                  padding: "3px 10px",
+                  background: "transparent",
+                  border: "1px solid rgba(244,86,78,0.4)",
+                  borderRadius: "var(--radius-md)",
+                  color: "var(--danger)",
+                  fontSize: "12px",
+                  cursor: "pointer",
+                  fontFamily: "var(--font-ui)",
+                }}
+              >
+                {errorAction === "retry" ? <RotateCcw size={12} /> : <Settings size={12} />}
+                {errorAction === "retry" ? t("errorRetry") : t("errorOpenSettings")}
+              </button>
+            )}
           </div>
         )}
       </motion.div>
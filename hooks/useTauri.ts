@@ -2,14 +2,115 @@
 
 import { useState, useEffect, useCallback } from "react";
 import { useLanguage } from "@/contexts/LanguageContext";
+import { TranslationKey } from "@/lib/translations";
 
-type ProcessingStatus = "idle" | "recording" | "transcribing" | "refining" | "injecting" | "done" | "error";
+type ProcessingStatus = "idle" | "recording" | "paused" | "transcribing" | "refining" | "injecting" | "done" | "cancelled" | "error";
+
+// Mirrors `PipelineState` in src-tauri/src/pipeline.rs
+export type PipelineState = "idle" | "recording" | "paused" | "transcribing" | "refining" | "injecting" | "cancelled" | "error" | "testing";
+
+const busyStatuses: ProcessingStatus[] = ["recording", "paused", "transcribing", "refining", "injecting"];
+
+// Mirrors `PipelineError::code` in src-tauri/src/error.rs
+export type PipelineErrorCode =
+    | "no_api_key" | "already_recording" | "not_recording" | "no_audio" | "no_speech"
+    | "audio_device" | "unauthorized" | "rate_limited" | "network" | "provider"
+    | "encoding" | "decoding" | "output" | "injection" | "config"
+    | "hotkey_invalid" | "hotkey_register" | "invalid_transition" | "cancelled"
+    | "bad_request" | "bad_token";
+
+// Mirrors `PipelineError` in src-tauri/src/error.rs
+export interface PipelineError {
+    code: PipelineErrorCode;
+    retryable: boolean;
+    message: string;
+    details: string | null;
+}
+
+const errorMessages: Record<PipelineErrorCode, TranslationKey> = {
+    no_api_key: "errorNoApiKey",
+    already_recording: "errorAlreadyRecording",
+    not_recording: "errorNotRecording",
+    no_audio: "errorNoAudio",
+    no_speech: "errorNoSpeech",
+    audio_device: "errorAudioDevice",
+    unauthorized: "errorUnauthorized",
+    rate_limited: "errorRateLimit",
+    network: "errorNetwork",
+    provider: "errorProvider",
+    encoding: "errorEncoding",
+    decoding: "errorDecoding",
+    output: "errorOutput",
+    injection: "errorInjection",
+    config: "errorConfig",
+    hotkey_invalid: "errorHotkeyInvalid",
+    hotkey_register: "errorHotkeyRegister",
+    invalid_transition: "errorBusy",
+    cancelled: "errorCancelled",
+    bad_request: "errorBadRequest",
+    bad_token: "errorBadToken",
+};
+
+// What the error display offers next to the message
+export type ErrorAction = "retry" | "settings";
+
+// Fixed in Settings, not by trying again
+const settingsErrors: PipelineErrorCode[] = ["no_api_key", "unauthorized", "config"];
+
+export const errorAction = (err: PipelineError | string | null): ErrorAction | null => {
+    if (!err || typeof err === "string") return null;
+    if (settingsErrors.includes(err.code)) return "settings";
+    return err.retryable ? "retry" : null;
+};
+
+// Mirrors `CaptureInfo` in src-tauri/src/capture.rs
+export interface AudioConfig {
+    device: string;
+    sample_rate: number;
+    channels: number;
+    sample_format: string;
+}
+
+// Mirrors `Quality` in src-tauri/src/meter.rs
+export type QualityVerdict = "ok" | "too_quiet" | "clipping" | "very_noisy";
+
+export interface RecordingQuality {
+    verdict: QualityVerdict;
+    speech_dbfs: number;
+    noise_floor_dbfs: number;
+    peak: number;
+    clipped: number;
+}
+
+// Mirrors `MicTest` in src-tauri/src/commands.rs
+export interface MicTest {
+    device: string;
+    quality: RecordingQuality;
+    // null when the test heard no speech; the device keeps previous_threshold
+    suggested_threshold: number | null;
+    previous_threshold: number;
+}
+
+export type SubtitleFormat = "srt" | "vtt";
+
+// Mirrors `FileTranscription` in src-tauri/src/commands.rs
+export interface FileTranscription {
+    entry: { id: string; timestamp: string; raw: string; refined: string; word_count: number; source?: string };
+    subtitle_path: string | null;
+}
 
 export function useTauri() {
     const { t } = useLanguage();
 
-    const translateError = (msg: string): string => {
-        if (!msg) return t("errorUnknown");
+    const translateError = (err: PipelineError | string): string => {
+        if (!err) return t("errorUnknown");
+        if (typeof err !== "string") {
+            const key = errorMessages[err.code];
+            if (!key) return t("errorUnknown");
+            // Both hotkey errors carry the rejected shortcut as `details`
+            return t(key).replace("{hotkey}", err.details ?? "");
+        }
+        const msg = err;
         const m = msg.toLowerCase();
         if (m.includes("no groq api key") || m.includes("api key"))
             return t("errorNoApiKey");
@@ -36,7 +137,15 @@ export function useTauri() {
     const [isRecording, setIsRecording] = useState(false);
     const [status, setStatus] = useState<ProcessingStatus>("idle");
     const [lastTranscription, setLastTranscription] = useState("");
-    const [rawError, setRawError] = useState<string | null>(null);
+    const [rawError, setRawError] = useState<PipelineError | string | null>(null);
+    // Mic problem found in the last recording, if any
+    const [qualityVerdict, setQualityVerdict] = useState<QualityVerdict>("ok");
+    // Seconds, when the last recording was stopped by `max_recording_secs`
+    const [recordingLimit, setRecordingLimit] = useState<number | null>(null);
+    // True while the pre-roll keeps the microphone open between recordings
+    const [prerollActive, setPrerollActive] = useState(false);
+    // The stream the last recording actually opened
+    const [audioConfig, setAudioConfig] = useState<AudioConfig | null>(null);
 
     useEffect(() => {
         const tauri = typeof window !== "undefined" && "__TAURI_INTERNALS__" in window;
@@ -53,6 +162,14 @@ export function useTauri() {
                     setIsRecording(true);
                     setStatus("recording");
                     setRawError(null);
+                    setQualityVerdict("ok");
+                    setRecordingLimit(null);
+                })
+            );
+
+            cleanupFns.push(
+                await listen<number>("recording-limit", (e) => {
+                    setRecordingLimit(e.payload);
                 })
             );
 
@@ -66,6 +183,10 @@ export function useTauri() {
                 // eslint-disable-next-line @typescript-eslint/no-explicit-any
                 await listen("processing-status", (e: any) => {
                     setStatus(e.payload as ProcessingStatus);
+                    if (e.payload === "cancelled") {
+                        setIsRecording(false);
+                        setTimeout(() => setStatus("idle"), 3000);
+                    }
                 })
             );
 
@@ -78,10 +199,56 @@ export function useTauri() {
                 })
             );
 
+            cleanupFns.push(
+                await listen<RecordingQuality>("recording-quality", (e) => {
+                    setQualityVerdict(e.payload.verdict);
+                })
+            );
+
+            cleanupFns.push(
+                // eslint-disable-next-line @typescript-eslint/no-explicit-any
+                await listen("preroll-active", (e: any) => {
+                    setPrerollActive(e.payload as boolean);
+                })
+            );
+            cleanupFns.push(
+                await listen<AudioConfig>("audio-config", (e) => {
+                    setAudioConfig(e.payload);
+                })
+            );
+            const { invoke } = await import("@tauri-apps/api/core");
+            invoke<boolean>("get_preroll_active").then(setPrerollActive).catch(() => {});
+
+            // The session state itself, also when it changed from the hotkey,
+            // the control socket or the local API
+            const applyPipelineState = (state: PipelineState) => {
+                setIsRecording(state === "recording" || state === "paused");
+                setStatus((prev) => {
+                    // "done", "cancelled" and "error" stay up for a few seconds after Idle
+                    // A microphone test shows its own progress on the settings page
+                    if (state === "idle" || state === "testing") return busyStatuses.includes(prev) ? "idle" : prev;
+                    // The `error` event brings the message
+                    if (state === "error") return prev;
+                    return state;
+                });
+            };
+            cleanupFns.push(
+                await listen<PipelineState>("pipeline-state", (e) => applyPipelineState(e.payload))
+            );
+            invoke<PipelineState>("get_pipeline_state").then(applyPipelineState).catch(() => {});
+
+            cleanupFns.push(
+                // Hands-free mode: each segment is pasted while recording continues
+                // eslint-disable-next-line @typescript-eslint/no-explicit-any
+                await listen("segment-complete", (e: any) => {
+                    setLastTranscription(e.payload.text as string);
+                })
+            );
+
             cleanupFns.push(
                 // eslint-disable-next-line @typescript-eslint/no-explicit-any
                 await listen("error", (e: any) => {
-                    setRawError(e.payload as string);
+                    setRawError(e.payload as PipelineError | string);
                     setStatus("error");
                     setIsRecording(false);
                     setTimeout(() => {
@@ -117,6 +284,18 @@ export function useTauri() {
         await invoke("stop_and_process");
     }, [invoke]);
 
+    const cancelRecording = useCallback(async () => {
+        await invoke("cancel_recording");
+    }, [invoke]);
+
+    const pauseRecording = useCallback(async () => {
+        await invoke("pause_recording");
+    }, [invoke]);
+
+    const resumeRecording = useCallback(async () => {
+        await invoke("resume_recording");
+    }, [invoke]);
+
     const getConfig = useCallback(async () => {
         return invoke("get_config");
     }, [invoke]);
@@ -144,6 +323,31 @@ export function useTauri() {
         [invoke]
     );
 
+    // `subtitles`: "srt" or "vtt" to also write a subtitle file next to the original
+    const transcribeFile = useCallback(
+        async (path: string, subtitles: SubtitleFormat | null = null) => {
+            return (await invoke("transcribe_file", { path, subtitles })) as FileTranscription | null;
+        },
+        [invoke]
+    );
+
+    const getInputDevices = useCallback(async () => {
+        const result = await invoke("get_input_devices");
+        return (result ?? { devices: [], default: null }) as { devices: string[]; default: string | null };
+    }, [invoke]);
+
+    const testMicrophone = useCallback(
+        async (device: string | null, playback: boolean) => {
+            return (await invoke("test_microphone", { device, playback })) as MicTest | null;
+        },
+        [invoke]
+    );
+
+    const getUploadFormats = useCallback(async () => {
+        const formats = await invoke("get_upload_formats");
+        return (formats ?? ["flac", "wav"]) as string[];
+    }, [invoke]);
+
     const getMicName = useCallback(async () => {
         const name = await invoke("get_mic_name");
         return (name ?? "") as string;
@@ -152,27 +356,50 @@ export function useTauri() {
     const statusLabel: Record<ProcessingStatus, string> = {
         idle: t("statusIdle"),
         recording: t("statusRecording"),
+        paused: t("statusPaused"),
         transcribing: t("statusTranscribing"),
         refining: t("statusRefining"),
         injecting: t("statusInjecting"),
         done: t("statusDone"),
+        cancelled: t("statusCancelled"),
         error: t("statusError"),
     };
 
+    const qualityHint: Record<Exclude<QualityVerdict, "ok">, string> = {
+        too_quiet: t("qualityTooQuietHint"),
+        clipping: t("qualityClippingHint"),
+        very_noisy: t("qualityVeryNoisyHint"),
+    };
+
     return {
         isTauri,
         isRecording,
         status,
         statusLabel: statusLabel[status],
         lastTranscription,
+        prerollActive,
+        audioConfig,
+        qualityWarning: qualityVerdict === "ok" ? null : qualityHint[qualityVerdict],
+        recordingLimitNotice: recordingLimit === null
+            ? null
+            : t("recordingLimitReached").replace("{minutes}", Math.round(recordingLimit / 60).toString()),
         error: rawError ? translateError(rawError) : null,
+        errorAction: errorAction(rawError),
         startRecording,
         stopAndProcess,
+        cancelRecording,
+        pauseRecording,
+        resumeRecording,
         getConfig,
         saveConfig,
         getHistory,
         clearHistory,
         deleteHistoryEntry,
         getMicName,
+        getUploadFormats,
+        getInputDevices,
+        testMicrophone,
+        transcribeFile,
+        translateError,
     };
 }
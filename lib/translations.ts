@@ -19,16 +19,29 @@ const en = {
   // Processing status labels
   statusIdle: "Ready",
   statusRecording: "Recording...",
+  statusPaused: "Paused",
   statusTranscribing: "Transcribing...",
   statusRefining: "Refining...",
   statusInjecting: "Injecting...",
   statusDone: "Done ✓",
+  statusCancelled: "Cancelled",
   statusError: "Error",
 
+  // Recording quality warnings (short form is shown in the overlay)
+  qualityTooQuiet: "Mic too quiet",
+  qualityTooQuietHint: "Your mic is very quiet — move closer or raise its input level.",
+  qualityClipping: "Mic clipping",
+  qualityClippingHint: "Your mic is distorting — lower its input level or move back a little.",
+  qualityVeryNoisy: "Very noisy",
+  qualityVeryNoisyHint: "Lots of background noise — try a quieter spot or turn on noise suppression.",
+  recordingLimitReached: "Recording stopped after {minutes} min, the maximum length. What you said was still transcribed.",
+
   // Home page
   welcomeTitle: "Welcome 👋",
   welcomeSubtitle: "Use {hotkey} or the button below to start recording",
   pressAgainToStop: "Press again to stop and process",
+  pauseRecording: "Pause",
+  resumeRecording: "Resume",
   pressToStart: "Press to start or use {hotkey}",
   statsAvgWpm: "Avg WPM",
   statsWpmSubtitle: "words per minute",
@@ -61,11 +74,45 @@ const en = {
   overlayLabel: "Where the waveform bar appears",
   overlayTop: "⬆ Top of Screen",
   overlayBottom: "⬇ Bottom of Screen",
+  modeSection: "Dictation Mode",
+  modeLabel: "What the hotkey does",
+  modePushToTalk: "Push to Talk",
+  modeContinuous: "Hands-free",
+  modePushToTalkHint: "Press to record, press again to transcribe and paste.",
+  modeContinuousHint: "Press to start listening. Each sentence is pasted after you pause; press again to stop.",
   aiSection: "AI Settings",
   sttLabel: "Speech → Text Model (Whisper)",
   sttRecommended: "whisper-large-v3 — Recommended ✦",
   sttFaster: "whisper-large-v3-turbo — Faster",
   sttHint: "whisper-large-v3 gives higher Arabic accuracy. Turbo is faster but slightly less accurate.",
+  processingSection: "Audio Processing",
+  processingHighPass: "High-pass Filter",
+  processingHighPassHint: "Removes rumble, desk thumps and electrical hum.",
+  processingNoise: "Noise Suppression",
+  processingNoiseHint: "Filters out fans, keyboards and office chatter.",
+  processingAgc: "Automatic Gain",
+  processingAgcHint: "Boosts quiet speakers to a consistent level.",
+  processingPreroll: "Pre-roll Buffer",
+  processingPrerollHint: "Keeps the mic open while idle so your first word isn't cut off. The last half second is held in memory only and is never saved or sent unless you start recording.",
+  micTest: "Microphone test",
+  micTestHint: "Records 4 seconds — stay quiet for a moment, then speak normally. The silence threshold found is saved for this microphone.",
+  micTestDefaultDevice: "Default microphone",
+  micTestPlayback: "Play back",
+  micTestRun: "Test",
+  micTestRunning: "Listening...",
+  micTestResult: "Speech {speech} dBFS · background {floor} dBFS · threshold {threshold}",
+  micTestFailed: "Microphone test failed",
+  micTestNoSpeech: "No speech heard above the background ({floor} dBFS). The threshold stays at {threshold} — speak during the test.",
+  prerollIndicator: "Mic open",
+  audioConfigSummary: "{rate} kHz · {channels} ch · {format}",
+  prerollIndicatorHint: "Pre-roll is on: the last half second is kept in memory only, and is used only when you start recording.",
+  toggleOn: "On",
+  toggleOff: "Off",
+  uploadFormatLabel: "Upload Format",
+  uploadFormatFlac: "FLAC — Lossless, about half the size ✦",
+  uploadFormatOpus: "Opus — Smallest, best on slow connections",
+  uploadFormatWav: "WAV — Uncompressed",
+  uploadFormatHint: "Smaller uploads mean faster results on slow Wi-Fi. FLAC keeps the audio bit-exact.",
   llmLabel: "Text Refinement Model (LLM)",
   llmOff: "🚫 Off — No LLM refinement",
   llmHintOff: "Text will be used as-is from Whisper without refinement (only vocabulary dictionary applied).",
@@ -73,6 +120,13 @@ const en = {
   systemPromptLabel: "System Prompt",
   systemPromptPlaceholder: "You are a specialized text editing assistant...",
   systemPromptHint: "This prompt determines how the LLM processes the transcribed text.",
+  localApiSection: "Local API",
+  localApiEnable: "HTTP API on this computer",
+  localApiHint: "Lets local tools such as browser extensions transcribe and refine text with your key, models and vocabulary. Listens on 127.0.0.1 only, and every request needs the token.",
+  localApiPort: "Port",
+  localApiToken: "Token",
+  localApiTokenPending: "Created when you save",
+  localApiNewToken: "New token",
 
   // Language section
   languageSection: "Language",
@@ -89,6 +143,12 @@ const en = {
   copiedBtn: "Copied",
   rawText: "Raw Text (before refinement)",
   historyEmpty: "History is empty. Start your first recording with Alt+Space 🎙️",
+  dropFilesHint: "Drop audio or video files here (WhatsApp voice notes, MP3, M4A, WAV, OGG, FLAC) to transcribe them into history.",
+  dropFilesActive: "Drop to transcribe",
+  fileTranscribing: "Transcribing {name}...",
+  subtitlesLabel: "Subtitles",
+  subtitlesNone: "None",
+  subtitlesSaved: "Subtitles saved to {path}",
   historyNoResults: "No search results found.",
 
   // Modes page
@@ -125,11 +185,26 @@ const en = {
   errorNoApiKey: "No Groq API Key set. Go to Settings to add it.",
   errorNoAudio: "No audio captured. Make sure your microphone is working and permissions are granted.",
   errorNoSpeech: "No clear speech detected. Try recording again in a quieter environment.",
+  errorDecoding: "This file couldn't be read. Supported: WAV, MP3, M4A/AAC, MP4, OGG/Vorbis, FLAC, and OGG/Opus in builds with the opus feature.",
+  errorOutput: "The subtitle file couldn't be saved next to the original. Check that the folder is writable.",
   errorNotRecording: "No active recording currently.",
   errorAlreadyRecording: "Recording is already in progress.",
   errorNetwork: "Internet connection error. Check your connection and try again.",
   errorUnauthorized: "Groq API key is invalid or expired. Check Settings.",
   errorRateLimit: "Usage limit exceeded. Wait a moment and try again.",
+  errorAudioDevice: "The microphone couldn't be opened or stopped working. Check that it is connected and not used by another app.",
+  errorProvider: "Groq couldn't process the request. Try again in a moment.",
+  errorEncoding: "The recording couldn't be prepared for upload.",
+  errorInjection: "The text couldn't be typed into the window. It is saved in History.",
+  errorConfig: "Settings couldn't be saved. Check that the config folder is writable.",
+  errorHotkeyInvalid: "The shortcut '{hotkey}' isn't valid. Try e.g. Ctrl+Space.",
+  errorHotkeyRegister: "Couldn't register the shortcut '{hotkey}'. Try a different one.",
+  errorBusy: "Still working on the previous dictation. Try again in a moment.",
+  errorCancelled: "Dictation cancelled.",
+  errorBadRequest: "The request was not understood.",
+  errorBadToken: "Missing or wrong API token.",
+  errorRetry: "Retry",
+  errorOpenSettings: "Open Settings",
 };
 
 const ar: typeof en = {
@@ -148,15 +223,27 @@ const ar: typeof en = {
 
   statusIdle: "جاهز",
   statusRecording: "يسجل...",
+  statusPaused: "متوقف مؤقتاً",
   statusTranscribing: "يحوّل الكلام...",
   statusRefining: "يحسّن النص...",
   statusInjecting: "يكتب...",
   statusDone: "تم ✓",
+  statusCancelled: "تم الإلغاء",
   statusError: "خطأ",
 
+  qualityTooQuiet: "الميكروفون منخفض",
+  qualityTooQuietHint: "صوت الميكروفون منخفض جداً — اقترب منه أو ارفع مستوى الإدخال.",
+  qualityClipping: "الصوت مشوّه",
+  qualityClippingHint: "الميكروفون يشوّه الصوت — اخفض مستوى الإدخال أو ابتعد قليلاً.",
+  qualityVeryNoisy: "ضوضاء عالية",
+  qualityVeryNoisyHint: "ضوضاء خلفية كثيرة — جرّب مكاناً أهدأ أو فعّل إزالة الضوضاء.",
+  recordingLimitReached: "توقف التسجيل بعد {minutes} دقيقة، وهي المدة القصوى. تم تحويل ما قلته إلى نص.",
+
   welcomeTitle: "مرحباً بك 👋",
   welcomeSubtitle: "استخدم {hotkey} أو الزر أدناه لبدء التسجيل",
   pressAgainToStop: "اضغط مرة أخرى للإيقاف والمعالجة",
+  pauseRecording: "إيقاف مؤقت",
+  resumeRecording: "استئناف",
   pressToStart: "اضغط للبدء أو استخدم {hotkey}",
   statsAvgWpm: "متوسط WPM",
   statsWpmSubtitle: "كلمة في الدقيقة",
@@ -188,11 +275,45 @@ const ar: typeof en = {
   overlayLabel: "مكان ظهور شريط الموجات الصوتية",
   overlayTop: "⬆ أعلى الشاشة",
   overlayBottom: "⬇ أسفل الشاشة",
+  modeSection: "وضع الإملاء",
+  modeLabel: "وظيفة الاختصار",
+  modePushToTalk: "اضغط للتحدث",
+  modeContinuous: "بدون يدين",
+  modePushToTalkHint: "اضغط للتسجيل، ثم اضغط مرة أخرى للتحويل واللصق.",
+  modeContinuousHint: "اضغط لبدء الاستماع. تُلصق كل جملة بعد توقفك عن الكلام؛ اضغط مرة أخرى للإيقاف.",
   aiSection: "إعدادات الذكاء الاصطناعي",
   sttLabel: "نموذج التحويل الصوت → نص (Whisper)",
   sttRecommended: "whisper-large-v3 — الموصى به ✦",
   sttFaster: "whisper-large-v3-turbo — أسرع",
   sttHint: "whisper-large-v3 يعطي دقة أعلى في العربية. الـ turbo أسرع ولكن دقته أقل قليلاً.",
+  processingSection: "معالجة الصوت",
+  processingHighPass: "فلتر الترددات المنخفضة",
+  processingHighPassHint: "يزيل الطنين وصوت خبط المكتب والتشويش الكهربائي.",
+  processingNoise: "إزالة الضوضاء",
+  processingNoiseHint: "يفلتر صوت المراوح والكيبورد وكلام المكتب.",
+  processingAgc: "ضبط مستوى الصوت تلقائياً",
+  processingAgcHint: "يرفع صوت المتحدث الهادئ لمستوى ثابت.",
+  processingPreroll: "التسجيل المسبق",
+  processingPrerollHint: "يبقي الميكروفون مفتوحاً أثناء الانتظار حتى لا تضيع أول كلمة. آخر نصف ثانية تُحفظ في الذاكرة فقط ولا تُحفظ أو تُرسل أبداً إلا عند بدء التسجيل.",
+  micTest: "اختبار الميكروفون",
+  micTestHint: "يسجل 4 ثوانٍ — اصمت لحظة ثم تكلم بشكل طبيعي. يُحفظ حد الصمت الناتج لهذا الميكروفون.",
+  micTestDefaultDevice: "الميكروفون الافتراضي",
+  micTestPlayback: "إعادة التشغيل",
+  micTestRun: "اختبار",
+  micTestRunning: "يستمع...",
+  micTestResult: "الكلام {speech} dBFS · الخلفية {floor} dBFS · الحد {threshold}",
+  micTestFailed: "فشل اختبار الميكروفون",
+  micTestNoSpeech: "لم يُسمع كلام أعلى من الخلفية ({floor} dBFS). بقي الحد على {threshold} — تكلم أثناء الاختبار.",
+  prerollIndicator: "الميكروفون مفتوح",
+  audioConfigSummary: "{rate} كيلوهرتز · {channels} قناة · {format}",
+  prerollIndicatorHint: "التسجيل المسبق مفعّل: آخر نصف ثانية تُحفظ في الذاكرة فقط، وتُستخدم فقط عند بدء التسجيل.",
+  toggleOn: "تشغيل",
+  toggleOff: "إيقاف",
+  uploadFormatLabel: "صيغة رفع الصوت",
+  uploadFormatFlac: "FLAC — بدون فقد، حوالي نصف الحجم ✦",
+  uploadFormatOpus: "Opus — الأصغر حجماً، الأفضل للاتصال البطيء",
+  uploadFormatWav: "WAV — بدون ضغط",
+  uploadFormatHint: "كلما صغر حجم الملف وصلت النتيجة أسرع على شبكات الواي فاي البطيئة. FLAC يحافظ على الصوت كما هو تماماً.",
   llmLabel: "نموذج تحسين النص (LLM)",
   llmOff: "🚫 Off — بدون تحسين LLM",
   llmHintOff: "النص سيُستخدم كما هو من Whisper بدون تحسين (مع تطبيق قاموس المفردات فقط).",
@@ -200,6 +321,13 @@ const ar: typeof en = {
   systemPromptLabel: "System Prompt",
   systemPromptPlaceholder: "أنت مساعد متخصص في تحرير النصوص...",
   systemPromptHint: "هذا البرومبت بيحدد كيفية تعامل الـ LLM مع النص المنقول.",
+  localApiSection: "الواجهة البرمجية المحلية",
+  localApiEnable: "واجهة HTTP على هذا الجهاز",
+  localApiHint: "تسمح للأدوات المحلية مثل إضافات المتصفح بتحويل الصوت وتحسين النص بمفتاحك ونماذجك وقاموسك. تستمع على 127.0.0.1 فقط، وكل طلب يحتاج الرمز.",
+  localApiPort: "المنفذ",
+  localApiToken: "الرمز",
+  localApiTokenPending: "يُنشأ عند الحفظ",
+  localApiNewToken: "رمز جديد",
 
   languageSection: "اللغة",
   languageLabel: "لغة الواجهة",
@@ -214,6 +342,12 @@ const ar: typeof en = {
   copiedBtn: "نُسخ",
   rawText: "النص الخام (قبل التحسين)",
   historyEmpty: "السجل فارغ. ابدأ التسجيل الأول باستخدام Alt+Space 🎙️",
+  dropFilesHint: "اسحب ملفات صوت أو فيديو هنا (رسائل واتساب الصوتية، MP3، M4A، WAV، OGG، FLAC) لتحويلها إلى نص في السجل.",
+  dropFilesActive: "أفلت الملفات لتحويلها",
+  fileTranscribing: "جاري تحويل {name}...",
+  subtitlesLabel: "ترجمة مصاحبة",
+  subtitlesNone: "بدون",
+  subtitlesSaved: "حُفظت الترجمة في {path}",
   historyNoResults: "لا توجد نتائج للبحث.",
 
   modesTitle: "الأوضاع",
@@ -247,11 +381,26 @@ const ar: typeof en = {
   errorNoApiKey: "لم يتم إضافة Groq API Key. اذهب إلى الإعدادات لإضافته.",
   errorNoAudio: "لم يتم التقاط أي صوت. تأكد من أن الميكروفون يعمل ومن صلاحيات الوصول.",
   errorNoSpeech: "لم يُكتشف كلام واضح. حاول التسجيل مرة أخرى في بيئة أهدأ.",
+  errorDecoding: "تعذّرت قراءة هذا الملف. الصيغ المدعومة: WAV، MP3، M4A/AAC، MP4، OGG/Vorbis، FLAC، وOGG/Opus في النسخ المبنية بخاصية opus.",
+  errorOutput: "تعذّر حفظ ملف الترجمة بجانب الملف الأصلي. تأكد أن المجلد قابل للكتابة.",
   errorNotRecording: "لا يوجد تسجيل نشط حالياً.",
   errorAlreadyRecording: "التسجيل جارٍ بالفعل.",
   errorNetwork: "خطأ في الاتصال بالإنترنت. تأكد من اتصالك وحاول مرة أخرى.",
   errorUnauthorized: "مفتاح Groq API غير صحيح أو منتهي الصلاحية. تحقق من الإعدادات.",
   errorRateLimit: "تجاوزت حد الاستخدام المسموح. انتظر لحظة وحاول مرة أخرى.",
+  errorAudioDevice: "تعذّر فتح الميكروفون أو توقف عن العمل. تأكد أنه متصل وغير مستخدم من تطبيق آخر.",
+  errorProvider: "تعذّر على Groq معالجة الطلب. حاول مرة أخرى بعد لحظة.",
+  errorEncoding: "تعذّر تجهيز التسجيل للرفع.",
+  errorInjection: "تعذّرت كتابة النص في النافذة. النص محفوظ في السجل.",
+  errorConfig: "تعذّر حفظ الإعدادات. تأكد أن مجلد الإعدادات قابل للكتابة.",
+  errorHotkeyInvalid: "صيغة الاختصار '{hotkey}' غير صحيحة. جرب مثلاً: Ctrl+Space",
+  errorHotkeyRegister: "فشل تسجيل الاختصار '{hotkey}'. جرب اختصاراً مختلفاً.",
+  errorBusy: "ما زال الإملاء السابق قيد المعالجة. حاول مرة أخرى بعد لحظة.",
+  errorCancelled: "تم إلغاء الإملاء.",
+  errorBadRequest: "لم يُفهم الطلب.",
+  errorBadToken: "رمز الواجهة البرمجية مفقود أو غير صحيح.",
+  errorRetry: "إعادة المحاولة",
+  errorOpenSettings: "فتح الإعدادات",
 };
 
 export const translations = { en, ar } as const;
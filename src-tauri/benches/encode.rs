@@ -0,0 +1,60 @@
+//! Encode time and payload size of each upload format.
+//!
+//!     cargo bench --bench encode
+//!
+//! Sizes are printed once up front; criterion reports the timings.
+
+use app_lib::encode::{encode, UploadFormat};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const RATE: u32 = 16_000;
+const SECONDS: usize = 10;
+
+/// Speech-like test signal: a gliding voiced harmonic series with a
+/// syllable-rate envelope, short pauses and a little background noise.
+fn speech_like() -> Vec<f32> {
+    let mut seed = 0x1234_5678u32;
+    (0..RATE as usize * SECONDS)
+        .map(|i| {
+            let t = i as f32 / RATE as f32;
+            let f0 = 120.0 + 30.0 * (t * 0.7 * std::f32::consts::TAU).sin();
+            let voiced: f32 = (1..=8)
+                .map(|h| (t * f0 * h as f32 * std::f32::consts::TAU).sin() / h as f32)
+                .sum();
+            let syllables = (t * 4.0 * std::f32::consts::TAU).sin().max(0.0);
+            let pause = if (t % 3.0) > 2.4 { 0.0 } else { 1.0 };
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let noise = (seed >> 8) as f32 / (1u32 << 24) as f32 - 0.5;
+            0.2 * voiced * syllables * pause + 0.004 * noise
+        })
+        .collect()
+}
+
+fn bench_encode(c: &mut Criterion) {
+    let samples = speech_like();
+    let formats = UploadFormat::available();
+
+    let wav_len = encode(&samples, RATE, UploadFormat::Wav).unwrap().len();
+    for &format in &formats {
+        let len = encode(&samples, RATE, format).unwrap().len();
+        println!(
+            "{:?}: {} KB for {}s ({:.0}% of WAV)",
+            format,
+            len / 1024,
+            SECONDS,
+            len as f64 * 100.0 / wav_len as f64
+        );
+    }
+
+    let mut group = c.benchmark_group("encode");
+    group.throughput(Throughput::Elements(samples.len() as u64));
+    for &format in &formats {
+        group.bench_with_input(BenchmarkId::from_parameter(format!("{:?}", format)), &samples, |b, s| {
+            b.iter(|| encode(s, RATE, format).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_encode);
+criterion_main!(benches);
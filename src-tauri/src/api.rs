@@ -0,0 +1,383 @@
+//! Optional local HTTP API on `127.0.0.1:<api_port>`, so other tools on this
+//! machine can use the user's Ektb pipeline without STT keys of their own.
+
+use crate::audio::TARGET_SAMPLE_RATE;
+use crate::commands::RecordingState;
+use crate::config;
+use crate::decode;
+use crate::error::PipelineError;
+use crate::transcribe;
+use base64::Engine;
+use futures_util::{SinkExt, StreamExt};
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::body::{Bytes, Incoming};
+use hyper::header::{self, HeaderValue};
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_tungstenite::tungstenite::protocol::WebSocketConfig;
+use hyper_tungstenite::tungstenite::Message;
+use hyper_tungstenite::{HyperWebsocket, HyperWebsocketStream};
+use hyper_util::rt::TokioIo;
+use serde::Deserialize;
+use serde_json::json;
+use std::convert::Infallible;
+use std::io;
+use std::sync::Mutex;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+
+/// Largest accepted audio upload.
+const MAX_UPLOAD_BYTES: usize = 100 * 1024 * 1024;
+
+/// Largest accepted `/refine` body.
+const MAX_TEXT_BYTES: usize = 1024 * 1024;
+
+/// Largest message a WebSocket client may send. Anything it sends is
+/// ignored, so this only bounds what a misbehaving client can make us buffer.
+const MAX_CLIENT_MESSAGE: usize = 64 * 1024;
+
+type Body = Full<Bytes>;
+
+/// The running server, if any. Managed state.
+pub struct ApiServer {
+    events: broadcast::Sender<String>,
+    running: Mutex<Option<(CancellationToken, JoinHandle<()>)>>,
+}
+
+impl ApiServer {
+    pub fn new(events: broadcast::Sender<String>) -> Self {
+        Self { events, running: Mutex::new(None) }
+    }
+}
+
+/// What every connection needs.
+#[derive(Clone)]
+struct Shared {
+    app: AppHandle,
+    events: broadcast::Sender<String>,
+    stop: CancellationToken,
+}
+
+/// Start, restart or stop the server to match the config. Called at startup
+/// and whenever the API settings are saved. Stopping also closes open
+/// connections, WebSockets included.
+pub fn apply_config(app: &AppHandle) {
+    let server = app.state::<ApiServer>();
+    let mut running = server.running.lock().unwrap();
+    let previous = running.take();
+
+    let (enabled, port) = {
+        let state = app.state::<RecordingState>();
+        let mut cfg = state.config.lock().unwrap();
+        if cfg.api_enabled && cfg.api_token.is_empty() {
+            cfg.api_token = new_token();
+            if let Err(e) = config::save_config(&cfg) {
+                log::error!("Failed to save API token: {}", e);
+            }
+        }
+        (cfg.api_enabled, cfg.api_port)
+    };
+
+    let shared = Shared { app: app.clone(), events: server.events.clone(), stop: CancellationToken::new() };
+    let stop = shared.stop.clone();
+    let task = tauri::async_runtime::spawn(async move {
+        // Let the old listener release the port first
+        if let Some((stop, task)) = previous {
+            stop.cancel();
+            let _ = task.await;
+        }
+        if !enabled {
+            return;
+        }
+        if let Err(e) = listen(shared.clone(), port).await {
+            log::error!("Local API could not listen on port {}: {}", port, e);
+            let err = PipelineError::Config(format!("Local API could not listen on port {}: {}", port, e));
+            shared.app.emit("error", &err).ok();
+        }
+    });
+    *running = Some((stop, task));
+}
+
+async fn listen(shared: Shared, port: u16) -> io::Result<()> {
+    let listener = tokio::net::TcpListener::bind((std::net::Ipv4Addr::LOCALHOST, port)).await?;
+    log::info!("Local API listening on http://127.0.0.1:{}", port);
+
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    log::warn!("Local API accept failed: {}", e);
+                    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+                    continue;
+                }
+            },
+            _ = shared.stop.cancelled() => {
+                log::info!("Local API stopped");
+                return Ok(());
+            }
+        };
+
+        let shared = shared.clone();
+        tauri::async_runtime::spawn(async move {
+            let stop = shared.stop.clone();
+            let service = hyper::service::service_fn(move |req| handle(req, shared.clone()));
+            let conn = hyper::server::conn::http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .with_upgrades();
+            tokio::select! {
+                result = conn => {
+                    if let Err(e) = result {
+                        log::debug!("Local API connection ended: {}", e);
+                    }
+                }
+                _ = stop.cancelled() => {}
+            }
+        });
+    }
+}
+
+/// No CORS headers are sent, so web pages can't call the API; native tools
+/// and browser extensions with host permissions can.
+async fn handle(req: Request<Incoming>, shared: Shared) -> Result<Response<Body>, Infallible> {
+    let response = match authorize(&req, &shared.app) {
+        Ok(()) => route(req, &shared).await.unwrap_or_else(|e| error_response(&e)),
+        Err(e) => error_response(&e),
+    };
+    Ok(response)
+}
+
+/// ```text
+/// POST /transcribe[?refine=false]   audio file as the body  → {"raw", "text"}
+/// POST /refine      {"text": …}                             → {"raw", "text"}
+/// GET  /history                                             → [HistoryEntry]
+/// GET  /events      WebSocket; each pipeline event is a text message
+///                   like the control socket's event lines
+/// ```
+///
+/// Errors are `{"error": {code, message, …}}` with a matching HTTP status.
+async fn route(req: Request<Incoming>, shared: &Shared) -> Result<Response<Body>, PipelineError> {
+    match (req.method(), req.uri().path()) {
+        (&Method::POST, "/transcribe") => transcribe(req, &shared.app).await,
+        (&Method::POST, "/refine") => refine(req, &shared.app).await,
+        (&Method::GET, "/history") => {
+            let state = shared.app.state::<RecordingState>();
+            let cfg = state.config.lock().unwrap();
+            Ok(json_response(StatusCode::OK, &json!(cfg.history)))
+        }
+        (&Method::GET, "/events") => upgrade(req, shared),
+        (method, path) => {
+            let err = PipelineError::BadRequest(format!("no route for {} {}", method, path));
+            Ok(json_response(StatusCode::NOT_FOUND, &json!({ "error": err })))
+        }
+    }
+}
+
+/// Every request needs `Authorization: Bearer <api_token>`. Browsers can't
+/// set headers on a WebSocket, so the `/events` upgrade may pass `?token=`
+/// instead; nothing else accepts it, keeping the token out of other URLs.
+///
+/// The token is read on every request, so a new one applies at once and a
+/// disabled API stops answering even on kept-alive connections.
+fn authorize(req: &Request<Incoming>, app: &AppHandle) -> Result<(), PipelineError> {
+    let state = app.state::<RecordingState>();
+    let cfg = state.config.lock().unwrap();
+    let events_upgrade = req.uri().path() == "/events" && hyper_tungstenite::is_upgrade_request(req);
+    let given = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or_else(|| query_param(req, "token").filter(|_| events_upgrade));
+    match given {
+        Some(token) if cfg.api_enabled && !cfg.api_token.is_empty() && same(token, &cfg.api_token) => Ok(()),
+        _ => Err(PipelineError::BadToken),
+    }
+}
+
+#[derive(Deserialize)]
+struct RefineRequest {
+    text: String,
+}
+
+async fn transcribe(req: Request<Incoming>, app: &AppHandle) -> Result<Response<Body>, PipelineError> {
+    let refine = query_param(&req, "refine") != Some("false");
+    let extension = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(extension_for);
+    let body = read_body(req, MAX_UPLOAD_BYTES).await?;
+
+    let samples = tauri::async_runtime::spawn_blocking(move || decode::decode_bytes(body, extension))
+        .await
+        .map_err(|e| PipelineError::Decoding(e.to_string()))?
+        .map_err(|e| PipelineError::Decoding(e.to_string()))?;
+    if samples.is_empty() {
+        return Err(PipelineError::NoAudio);
+    }
+    log::info!("Local API: transcribing {:.1}s", samples.len() as f32 / TARGET_SAMPLE_RATE as f32);
+
+    let config = app.state::<RecordingState>().config.lock().unwrap().clone();
+    let raw = transcribe::transcribe_clip(&samples, TARGET_SAMPLE_RATE, &config).await?;
+    let text = if refine {
+        transcribe::refine_transcript(&raw, &config).await?
+    } else {
+        raw.clone()
+    };
+    Ok(json_response(StatusCode::OK, &json!({ "raw": raw, "text": text })))
+}
+
+async fn refine(req: Request<Incoming>, app: &AppHandle) -> Result<Response<Body>, PipelineError> {
+    let body = read_body(req, MAX_TEXT_BYTES).await?;
+    let request: RefineRequest =
+        serde_json::from_slice(&body).map_err(|e| PipelineError::BadRequest(e.to_string()))?;
+    let raw = request.text.trim().to_string();
+    if raw.is_empty() {
+        return Err(PipelineError::BadRequest("text is empty".into()));
+    }
+
+    let config = app.state::<RecordingState>().config.lock().unwrap().clone();
+    // With the LLM off, refining only applies the vocabulary: no key needed
+    if config.llm_model != "off" && config.groq_api_key.is_empty() {
+        return Err(PipelineError::NoApiKey);
+    }
+    let text = transcribe::refine_transcript(&raw, &config).await?;
+    Ok(json_response(StatusCode::OK, &json!({ "raw": raw, "text": text })))
+}
+
+/// Answer a WebSocket handshake on `/events`, then stream events over the
+/// upgraded connection.
+fn upgrade(mut req: Request<Incoming>, shared: &Shared) -> Result<Response<Body>, PipelineError> {
+    if !hyper_tungstenite::is_upgrade_request(&req) {
+        return Err(PipelineError::BadRequest("/events expects a WebSocket upgrade".into()));
+    }
+    let config = WebSocketConfig::default()
+        .max_message_size(Some(MAX_CLIENT_MESSAGE))
+        .max_frame_size(Some(MAX_CLIENT_MESSAGE));
+    let (response, websocket) = hyper_tungstenite::upgrade(&mut req, Some(config))
+        .map_err(|e| PipelineError::BadRequest(format!("WebSocket handshake: {}", e)))?;
+
+    let events = shared.events.subscribe();
+    let stop = shared.stop.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = stream_events(websocket, events, stop).await {
+            log::debug!("Local API WebSocket ended: {}", e);
+        }
+    });
+    Ok(response)
+}
+
+/// Forward events as text messages until the client closes, the API stops
+/// or the app exits. Pings are answered by tungstenite; other client
+/// messages are ignored.
+async fn stream_events(
+    websocket: HyperWebsocket,
+    mut events: broadcast::Receiver<String>,
+    stop: CancellationToken,
+) -> Result<(), hyper_tungstenite::tungstenite::Error> {
+    let mut ws: HyperWebsocketStream = websocket.await?;
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(line) => ws.send(Message::Text(line.into())).await?,
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    log::warn!("Local API WebSocket missed {} events", n);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            message = ws.next() => match message {
+                // Reading on queues the reply to a close; sending it ends the stream
+                Some(Ok(Message::Close(_))) => {
+                    ws.flush().await.ok();
+                    return Ok(());
+                }
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e),
+                // Connection dropped without a close frame
+                None => return Ok(()),
+            },
+            _ = stop.cancelled() => break,
+        }
+    }
+    ws.close(None).await
+}
+
+/// 32 random bytes, URL-safe so it also works as `?token=`.
+fn new_token() -> String {
+    use ring::rand::SecureRandom;
+    let mut bytes = [0u8; 32];
+    ring::rand::SystemRandom::new()
+        .fill(&mut bytes)
+        .expect("system random number generator");
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Token comparison that takes as long for a near miss as for a wild guess.
+fn same(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn query_param<'a>(req: &'a Request<Incoming>, name: &str) -> Option<&'a str> {
+    req.uri()
+        .query()?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+}
+
+/// A decoder hint from the upload's `Content-Type`; the data decides anyway.
+fn extension_for(content_type: &str) -> Option<&'static str> {
+    let mime = content_type.split(';').next()?.trim();
+    Some(match mime {
+        "audio/wav" | "audio/x-wav" | "audio/wave" => "wav",
+        "audio/mpeg" | "audio/mp3" => "mp3",
+        "audio/mp4" | "audio/m4a" | "audio/x-m4a" | "video/mp4" => "mp4",
+        "audio/aac" => "aac",
+        "audio/flac" | "audio/x-flac" => "flac",
+        "audio/ogg" | "audio/opus" => "ogg",
+        _ => return None,
+    })
+}
+
+async fn read_body(req: Request<Incoming>, limit: usize) -> Result<Bytes, PipelineError> {
+    Limited::new(req.into_body(), limit)
+        .collect()
+        .await
+        .map(|body| body.to_bytes())
+        .map_err(|e| PipelineError::BadRequest(format!("reading body: {}", e)))
+}
+
+fn status_for(err: &PipelineError) -> StatusCode {
+    match err {
+        PipelineError::BadToken => StatusCode::UNAUTHORIZED,
+        PipelineError::BadRequest(_) | PipelineError::Decoding(_) | PipelineError::NoAudio => {
+            StatusCode::BAD_REQUEST
+        }
+        PipelineError::NoSpeech => StatusCode::UNPROCESSABLE_ENTITY,
+        PipelineError::NoApiKey | PipelineError::Config(_) => StatusCode::SERVICE_UNAVAILABLE,
+        PipelineError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+        PipelineError::Unauthorized(_) | PipelineError::Network(_) | PipelineError::Provider { .. } => {
+            StatusCode::BAD_GATEWAY
+        }
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+fn error_response(err: &PipelineError) -> Response<Body> {
+    json_response(status_for(err), &json!({ "error": err }))
+}
+
+fn json_response(status: StatusCode, value: &serde_json::Value) -> Response<Body> {
+    let mut response = Response::new(Full::new(Bytes::from(value.to_string())));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
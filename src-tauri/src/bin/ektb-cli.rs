@@ -0,0 +1,264 @@
+//! Headless Ektb: the same STT and refinement pipeline as the app, driven
+//! from the command line. Uses the app's config file (API key, models,
+//! vocabulary, VAD and calibrated mic thresholds) but never writes to it.
+//!
+//!     ektb-cli transcribe <file> [--subtitles srt|vtt]
+//!     ektb-cli record [--device <name>] [--silence-ms <ms>]
+//!     ektb-cli refine              < text
+//!
+//! Common flags: `--json` prints `{"raw": …, "text": …}` instead of plain
+//! text, and `--no-refine` skips the LLM step. Status goes to stderr, so
+//! stdout carries only the result.
+
+use app_lib::audio::TARGET_SAMPLE_RATE;
+use app_lib::capture;
+use app_lib::config::{self, AppConfig};
+use app_lib::error::PipelineError;
+use app_lib::groq::TranscriptSegment;
+use app_lib::subtitles::{self, SubtitleFormat};
+use app_lib::{decode, transcribe, vad};
+use std::io::{IsTerminal, Read};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const USAGE: &str = "\
+Usage:
+  ektb-cli transcribe <file> [--subtitles srt|vtt] [--json] [--no-refine]
+  ektb-cli record [--device <name>] [--silence-ms <ms>] [--json] [--no-refine]
+  ektb-cli refine [--json]            (reads text from stdin)
+
+record stops when you press Enter, or after --silence-ms of silence
+following speech (default 1500).";
+
+/// How often `record` checks for the end of speech.
+const POLL_MS: u64 = 200;
+
+/// Default trailing silence that ends `record`.
+const DEFAULT_SILENCE_MS: u32 = 1500;
+
+struct Options {
+    json: bool,
+    refine: bool,
+    subtitles: Option<SubtitleFormat>,
+    device: Option<String>,
+    silence_ms: u32,
+    positional: Vec<String>,
+}
+
+/// The printed result.
+struct Output {
+    raw: String,
+    text: String,
+    subtitle_path: Option<String>,
+}
+
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Some((command, rest)) = args.split_first() else {
+        eprintln!("{}", USAGE);
+        std::process::exit(2);
+    };
+    let opts = match parse_options(rest) {
+        Ok(opts) => opts,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let config = config::load_config();
+    let result = match command.as_str() {
+        "transcribe" => run_transcribe(&opts, &config).await,
+        "record" => run_record(&opts, &config).await,
+        "refine" => run_refine(&config).await,
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            return;
+        }
+        other => {
+            eprintln!("Unknown command '{}'\n\n{}", other, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    match result {
+        Ok(out) => print_output(&out, opts.json),
+        Err(e) => {
+            if opts.json {
+                println!("{}", serde_json::json!({ "error": e }));
+            } else {
+                eprintln!("Error: {}", e);
+            }
+            std::process::exit(1);
+        }
+    }
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut opts = Options {
+        json: false,
+        refine: true,
+        subtitles: None,
+        device: None,
+        silence_ms: DEFAULT_SILENCE_MS,
+        positional: Vec::new(),
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| args.next().cloned().ok_or_else(|| format!("{} needs a value", flag));
+        match arg.as_str() {
+            "--json" => opts.json = true,
+            "--no-refine" => opts.refine = false,
+            "--subtitles" => {
+                let name = value(arg)?;
+                opts.subtitles = Some(
+                    SubtitleFormat::from_name(&name).ok_or_else(|| format!("Unknown subtitle format '{}'", name))?,
+                );
+            }
+            "--device" => opts.device = Some(value(arg)?),
+            "--silence-ms" => {
+                opts.silence_ms = value(arg)?.parse().map_err(|_| "--silence-ms needs a number".to_string())?;
+            }
+            flag if flag.starts_with("--") => return Err(format!("Unknown option '{}'", flag)),
+            _ => opts.positional.push(arg.clone()),
+        }
+    }
+    Ok(opts)
+}
+
+async fn run_transcribe(opts: &Options, config: &AppConfig) -> Result<Output, PipelineError> {
+    let [file] = opts.positional.as_slice() else {
+        return Err(PipelineError::Config("transcribe takes exactly one file".into()));
+    };
+    let path = Path::new(file);
+    let samples = decode::decode_file(path).map_err(|e| PipelineError::Decoding(e.to_string()))?;
+    if samples.is_empty() {
+        return Err(PipelineError::NoAudio);
+    }
+    eprintln!("Decoded {:.1}s, transcribing…", samples.len() as f32 / TARGET_SAMPLE_RATE as f32);
+
+    let Some(format) = opts.subtitles else {
+        return transcribe_samples(&samples, opts, config).await;
+    };
+
+    let segments = transcribe::transcribe_clip_timed(&samples, TARGET_SAMPLE_RATE, config).await?;
+    let refined = if opts.refine {
+        transcribe::refine_segments(&segments, config).await?
+    } else {
+        segments.clone()
+    };
+    let out = path.with_extension(format.extension());
+    std::fs::write(&out, subtitles::write(&refined, format))
+        .map_err(|e| PipelineError::Output(format!("{}: {}", out.display(), e)))?;
+    eprintln!("Subtitles written to {}", out.display());
+
+    let join = |segs: &[TranscriptSegment]| segs.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join(" ");
+    Ok(Output {
+        raw: join(&segments),
+        text: join(&refined),
+        subtitle_path: Some(out.to_string_lossy().into_owned()),
+    })
+}
+
+async fn run_record(opts: &Options, config: &AppConfig) -> Result<Output, PipelineError> {
+    let samples = Arc::new(Mutex::new(Vec::new()));
+    let running = Arc::new(AtomicBool::new(false));
+    let info = capture::start_test_capture(opts.device.as_deref(), Arc::clone(&samples), Arc::clone(&running))
+        .await
+        .map_err(|e| PipelineError::AudioDevice(e.to_string()))?;
+
+    let config = config.for_device(Some(&info.device));
+    let enter = watch_enter();
+    eprintln!("Recording from {} — press Enter to stop, or just stop talking.", info.device);
+
+    let mut vad_config = config.vad.clone();
+    vad_config.segment_pause_ms = opts.silence_ms;
+    let max_samples = config.max_recording_secs as usize * TARGET_SAMPLE_RATE as usize;
+    // Samples before this have been judged leading silence
+    let mut cursor = 0;
+    loop {
+        tokio::time::sleep(Duration::from_millis(POLL_MS)).await;
+        if enter.load(Ordering::SeqCst) || !running.load(Ordering::SeqCst) {
+            break;
+        }
+        let pending = samples.lock().unwrap()[cursor..].to_vec();
+        if let Some(cut) = vad::next_cut(&pending, TARGET_SAMPLE_RATE, &vad_config) {
+            if cut.speech {
+                break;
+            }
+            cursor += cut.end;
+        }
+        if max_samples > 0 && cursor + pending.len() >= max_samples {
+            eprintln!("Reached the {}s limit.", config.max_recording_secs);
+            break;
+        }
+    }
+
+    // Same flush delay as the app
+    running.store(false, Ordering::SeqCst);
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    let samples = std::mem::take(&mut *samples.lock().unwrap());
+    if samples.is_empty() {
+        return Err(PipelineError::NoAudio);
+    }
+    eprintln!("Transcribing…");
+    transcribe_samples(&samples, opts, &config).await
+}
+
+async fn run_refine(config: &AppConfig) -> Result<Output, PipelineError> {
+    let mut raw = String::new();
+    std::io::stdin()
+        .read_to_string(&mut raw)
+        .map_err(|e| PipelineError::Config(format!("Reading stdin: {}", e)))?;
+    let raw = raw.trim().to_string();
+    if raw.is_empty() {
+        return Err(PipelineError::NoSpeech);
+    }
+    // With the LLM off, refining only applies the vocabulary: no key needed
+    if config.llm_model != "off" && config.groq_api_key.is_empty() {
+        return Err(PipelineError::NoApiKey);
+    }
+    let text = transcribe::refine_transcript(&raw, config).await?;
+    Ok(Output { raw, text, subtitle_path: None })
+}
+
+async fn transcribe_samples(samples: &[f32], opts: &Options, config: &AppConfig) -> Result<Output, PipelineError> {
+    let raw = transcribe::transcribe_clip(samples, TARGET_SAMPLE_RATE, config).await?;
+    let text = if opts.refine {
+        transcribe::refine_transcript(&raw, config).await?
+    } else {
+        raw.clone()
+    };
+    Ok(Output { raw, text, subtitle_path: None })
+}
+
+/// Set once Enter is pressed. Only watches an interactive terminal: piped
+/// stdin would hit EOF straight away and end every recording at once.
+fn watch_enter() -> Arc<AtomicBool> {
+    let pressed = Arc::new(AtomicBool::new(false));
+    if std::io::stdin().is_terminal() {
+        let flag = Arc::clone(&pressed);
+        std::thread::spawn(move || {
+            let mut line = String::new();
+            if std::io::stdin().read_line(&mut line).is_ok_and(|n| n > 0) {
+                flag.store(true, Ordering::SeqCst);
+            }
+        });
+    }
+    pressed
+}
+
+fn print_output(out: &Output, json: bool) {
+    if json {
+        let mut value = serde_json::json!({ "raw": out.raw, "text": out.text });
+        if let Some(path) = &out.subtitle_path {
+            value["subtitle_path"] = serde_json::json!(path);
+        }
+        println!("{}", value);
+    } else {
+        println!("{}", out.text);
+    }
+}
@@ -0,0 +1,485 @@
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, Sample, SampleFormat, SizedSample};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use crate::audio::TARGET_SAMPLE_RATE;
+use crate::meter::{Meter, MeterReading};
+use crate::resample::{self, Resampler};
+use crate::sink::Sink;
+use anyhow::{anyhow, Result};
+
+/// Ring buffer capacity between the audio callback and the consumer thread.
+/// Two seconds at 48 kHz — far more than the consumer ever falls behind.
+const RING_CAPACITY: usize = 96_000;
+
+/// How often the consumer thread drains the ring buffer.
+const DRAIN_INTERVAL_MS: u64 = 10;
+
+/// The pre-roll isn't latency sensitive, so it wakes up less often.
+const ROLLING_DRAIN_INTERVAL_MS: u64 = 100;
+
+/// How often `audio-level` and `audio-meter` events are emitted.
+const LEVEL_INTERVAL_MS: u64 = 50;
+
+/// Capturing close to the target rate means less resampling work.
+const PREFERRED_SAMPLE_RATE: u32 = TARGET_SAMPLE_RATE;
+
+/// The stream configuration actually opened. Logged, and emitted to the UI
+/// as `audio-config` when recording starts.
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptureInfo {
+    pub device: String,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub sample_format: String,
+}
+
+/// What the consumer thread reports while capturing.
+pub enum CaptureEvent {
+    /// A meter reading, every `LEVEL_INTERVAL_MS`.
+    Level(MeterReading),
+    /// The stream died; capture has stopped.
+    Failed(String),
+}
+
+type Notify = Box<dyn Fn(CaptureEvent) + Send>;
+
+/// Where a running capture delivers its audio, and the flags that steer it.
+/// Shared with the consumer thread, so a pre-roll can become a recording
+/// without reopening the device.
+struct Route {
+    sink: Sink,
+    running: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+    notify: Option<Notify>,
+    drain_interval_ms: u64,
+}
+
+/// A pre-roll capture, from [`start_rolling_capture`].
+pub struct RollingCapture {
+    route: Arc<Mutex<Route>>,
+    pub info: CaptureInfo,
+}
+
+impl RollingCapture {
+    /// False once the stream has died (e.g. the mic was unplugged).
+    pub fn is_running(&self) -> bool {
+        self.route.lock().unwrap().running.load(Ordering::SeqCst)
+    }
+
+    /// Close the stream and discard the buffer.
+    pub fn stop(self) {
+        let mut route = self.route.lock().unwrap();
+        route.running.store(false, Ordering::SeqCst);
+        route.sink.clear();
+    }
+
+    /// Keep the stream open and make it the recording: `samples` gets the
+    /// buffered audio followed by everything captured from now on, with no
+    /// gap and nothing doubled at the seam. From here on the capture follows
+    /// `is_recording` and `paused` and reports to `notify`, as with
+    /// [`start_capture`]. `None` if the stream has already died.
+    pub fn into_recording(
+        self,
+        samples: Arc<Mutex<Vec<f32>>>,
+        is_recording: Arc<AtomicBool>,
+        paused: Arc<AtomicBool>,
+        notify: impl Fn(CaptureEvent) + Send + 'static,
+    ) -> Option<CaptureInfo> {
+        let mut route = self.route.lock().unwrap();
+        if !route.running.load(Ordering::SeqCst) {
+            return None;
+        }
+        route.sink.switch_to(samples);
+        is_recording.store(true, Ordering::SeqCst);
+        route.running = is_recording;
+        route.paused = paused;
+        route.notify = Some(Box::new(notify));
+        route.drain_interval_ms = DRAIN_INTERVAL_MS;
+        drop(route);
+        Some(self.info)
+    }
+}
+
+/// Start audio capture on a background thread.
+///
+/// Resolves once the stream is actually running, so a busy or missing
+/// microphone is reported to the caller instead of panicking the thread.
+/// If the stream later fails (e.g. the mic is unplugged), capture stops and
+/// `notify` gets `CaptureEvent::Failed` with the reason.
+///
+/// The cpal callback runs on a real-time thread, so it only converts to f32,
+/// mixes down to mono and pushes into a lock-free SPSC ring buffer — no
+/// allocation, no locks. A consumer thread drains the ring every 10 ms,
+/// resamples to `TARGET_SAMPLE_RATE` into `samples`, and passes a meter
+/// reading (RMS, peak, clipping, noise floor; see `meter.rs`) to `notify`
+/// every ~50 ms so the overlay can animate bars in response to actual
+/// microphone input.
+///
+/// While `paused` is set the consumer thread drops incoming audio, so
+/// nothing is appended but the stream (and the session) stays open.
+pub async fn start_capture(
+    samples: Arc<Mutex<Vec<f32>>>,
+    is_recording: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+    notify: impl Fn(CaptureEvent) + Send + 'static,
+) -> Result<CaptureInfo> {
+    let route = Route {
+        sink: Sink::Append(samples),
+        running: is_recording,
+        paused,
+        notify: Some(Box::new(notify)),
+        drain_interval_ms: DRAIN_INTERVAL_MS,
+    };
+    open_capture(None, route).await.map(|(info, _)| info)
+}
+
+/// Capture from the named input device (the default if `None`) into
+/// `samples` until `running` is cleared. For the microphone test and
+/// `ektb-cli`: no events, and nothing touches the recording state.
+pub async fn start_test_capture(
+    device: Option<&str>,
+    samples: Arc<Mutex<Vec<f32>>>,
+    running: Arc<AtomicBool>,
+) -> Result<CaptureInfo> {
+    let route = Route {
+        sink: Sink::Append(samples),
+        running,
+        paused: Arc::new(AtomicBool::new(false)),
+        notify: None,
+        drain_interval_ms: DRAIN_INTERVAL_MS,
+    };
+    open_capture(device, route).await.map(|(info, _)| info)
+}
+
+/// Capture from the default device, keeping only the newest `capacity`
+/// samples, until stopped or turned into a recording. Emits no events at
+/// all: the pre-roll must stay invisible to the recording logic, and a
+/// failure just ends it (logged).
+pub async fn start_rolling_capture(capacity: usize) -> Result<RollingCapture> {
+    let route = Route {
+        sink: Sink::Rolling(VecDeque::with_capacity(capacity), capacity),
+        running: Arc::new(AtomicBool::new(false)),
+        paused: Arc::new(AtomicBool::new(false)),
+        notify: None,
+        drain_interval_ms: ROLLING_DRAIN_INTERVAL_MS,
+    };
+    let (info, route) = open_capture(None, route).await?;
+    Ok(RollingCapture { route, info })
+}
+
+async fn open_capture(device_name: Option<&str>, route: Route) -> Result<(CaptureInfo, Arc<Mutex<Route>>)> {
+    let host = cpal::default_host();
+    let device = match device_name {
+        Some(name) => host
+            .input_devices()?
+            .find(|d| d.name().is_ok_and(|n| n == name))
+            .ok_or_else(|| anyhow!("Input device '{}' not found", name))?,
+        None => host
+            .default_input_device()
+            .ok_or_else(|| anyhow!("No default input device found"))?,
+    };
+
+    let config = choose_input_config(&device)?;
+    let sample_format = config.sample_format();
+    let info = CaptureInfo {
+        device: device.name().unwrap_or_default(),
+        sample_rate: config.sample_rate().0,
+        channels: config.channels(),
+        sample_format: format!("{:?}", sample_format),
+    };
+
+    log::info!(
+        "Audio device: {}, sample_rate: {}, channels: {}, format: {}",
+        info.device,
+        info.sample_rate,
+        info.channels,
+        info.sample_format
+    );
+
+    let (producer, mut consumer) = rtrb::RingBuffer::<f32>::new(RING_CAPACITY);
+    let overruns = Arc::new(AtomicUsize::new(0));
+    let stream_error: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+    let capture = CallbackState {
+        producer,
+        channels: info.channels as usize,
+        overruns: Arc::clone(&overruns),
+    };
+
+    route.running.store(true, Ordering::SeqCst);
+    let route = Arc::new(Mutex::new(route));
+    let thread_route = Arc::clone(&route);
+    let device_rate = info.sample_rate;
+    let (ready_tx, ready_rx) = tokio::sync::oneshot::channel::<Result<()>>();
+
+    std::thread::spawn(move || {
+        let route = thread_route;
+        let stream_config: cpal::StreamConfig = config.into();
+        let err_slot = Arc::clone(&stream_error);
+        let stream = match sample_format {
+            SampleFormat::F32 => build_stream::<f32>(&device, &stream_config, capture, err_slot),
+            SampleFormat::I16 => build_stream::<i16>(&device, &stream_config, capture, err_slot),
+            SampleFormat::U16 => build_stream::<u16>(&device, &stream_config, capture, err_slot),
+            SampleFormat::I32 => build_stream::<i32>(&device, &stream_config, capture, err_slot),
+            SampleFormat::I8 => build_stream::<i8>(&device, &stream_config, capture, err_slot),
+            SampleFormat::U8 => build_stream::<u8>(&device, &stream_config, capture, err_slot),
+            other => Err(anyhow!("Unsupported sample format {:?}", other)),
+        }
+        .and_then(|stream| {
+            stream.play().map_err(|e| anyhow!("Failed to start audio stream: {}", e))?;
+            Ok(stream)
+        });
+
+        let stream = match stream {
+            Ok(stream) => {
+                ready_tx.send(Ok(())).ok();
+                stream
+            }
+            Err(e) => {
+                route.lock().unwrap().running.store(false, Ordering::SeqCst);
+                ready_tx.send(Err(e)).ok();
+                return;
+            }
+        };
+
+        // Consumer: drain the ring, resample into the recording buffer, emit levels
+        let mut resampler = Resampler::new(device_rate, TARGET_SAMPLE_RATE);
+        let mut resampled = Vec::new();
+        let mut meter = Meter::default();
+        let mut since_level = 0u64;
+        loop {
+            let (running, drain_interval_ms) = {
+                let route = route.lock().unwrap();
+                (route.running.load(Ordering::SeqCst), route.drain_interval_ms)
+            };
+            std::thread::sleep(std::time::Duration::from_millis(drain_interval_ms));
+
+            // Held until this round is delivered, so a hand-over lands between rounds
+            let mut route = route.lock().unwrap();
+            let available = consumer.slots();
+            if available > 0 {
+                if let Ok(chunk) = consumer.read_chunk(available) {
+                    // While paused the audio is dropped, but the stream stays open
+                    if !route.paused.load(Ordering::Relaxed) {
+                        let (a, b) = chunk.as_slices();
+                        meter.push(a);
+                        meter.push(b);
+                        resampler.process(a, &mut resampled);
+                        resampler.process(b, &mut resampled);
+                    }
+                    chunk.commit_all();
+                }
+            }
+            if !running {
+                resampler.flush(&mut resampled);
+            }
+            if !resampled.is_empty() {
+                route.sink.push(&resampled);
+                resampled.clear();
+            }
+
+            since_level += drain_interval_ms;
+            if since_level >= LEVEL_INTERVAL_MS {
+                since_level = 0;
+                if let (Some(notify), Some(reading)) = (&route.notify, meter.reading()) {
+                    notify(CaptureEvent::Level(reading));
+                }
+            }
+
+            // One last drain after the stop signal so the tail isn't lost
+            if !running {
+                break;
+            }
+
+            // The stream died under us — stop capturing and tell the app
+            if let Some(err) = stream_error.lock().unwrap().take() {
+                log::error!("Audio stream failed, stopping capture: {}", err);
+                route.running.store(false, Ordering::SeqCst);
+                if let Some(notify) = &route.notify {
+                    notify(CaptureEvent::Failed(err));
+                }
+                break;
+            }
+        }
+
+        let dropped = overruns.load(Ordering::Relaxed);
+        if dropped > 0 {
+            log::warn!("Audio ring buffer overran, dropped {} samples", dropped);
+        }
+
+        drop(stream);
+    });
+
+    match ready_rx.await {
+        Ok(Ok(())) => Ok((info, route)),
+        Ok(Err(e)) => Err(e),
+        Err(_) => {
+            route.lock().unwrap().running.store(false, Ordering::SeqCst);
+            Err(anyhow!("Audio thread exited before the stream started"))
+        }
+    }
+}
+
+/// Everything the real-time callback owns.
+struct CallbackState {
+    producer: rtrb::Producer<f32>,
+    channels: usize,
+    overruns: Arc<AtomicUsize>,
+}
+
+/// Open an input stream for sample type `T`, converting each frame to mono f32.
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut cb: CallbackState,
+    stream_error: Arc<Mutex<Option<String>>>,
+) -> Result<cpal::Stream>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let stream = device.build_input_stream(
+        config,
+        move |data: &[T], _info: &cpal::InputCallbackInfo| {
+            // Mix down to mono straight into the ring buffer
+            let frames = data.len() / cb.channels;
+            let n = frames.min(cb.producer.slots());
+            if let Ok(chunk) = cb.producer.write_chunk_uninit(n) {
+                chunk.fill_from_iter(data.chunks(cb.channels).map(|ch| {
+                    ch.iter().map(|&s| f32::from_sample(s)).sum::<f32>() / ch.len() as f32
+                }));
+            }
+            if n < frames {
+                cb.overruns.fetch_add(frames - n, Ordering::Relaxed);
+            }
+        },
+        move |err| {
+            log::error!("Audio stream error: {}", err);
+            *stream_error.lock().unwrap() = Some(err.to_string());
+        },
+        None,
+    )?;
+    Ok(stream)
+}
+
+/// Pick the supported input config closest to 16 kHz mono, preferring
+/// float, then 16-bit formats. Falls back to the device default.
+fn choose_input_config(device: &cpal::Device) -> Result<cpal::SupportedStreamConfig> {
+    let default = device.default_input_config()?;
+
+    let ranges = match device.supported_input_configs() {
+        Ok(r) => r,
+        Err(e) => {
+            log::warn!("Could not list input configs ({}), using default", e);
+            return Ok(default);
+        }
+    };
+
+    let best = ranges
+        .filter_map(|range| {
+            let format_rank = match range.sample_format() {
+                SampleFormat::F32 => 0,
+                SampleFormat::I16 => 1,
+                SampleFormat::U16 => 2,
+                SampleFormat::I32 => 3,
+                SampleFormat::I8 | SampleFormat::U8 => 4,
+                _ => return None,
+            };
+            let rate = PREFERRED_SAMPLE_RATE
+                .clamp(range.min_sample_rate().0, range.max_sample_rate().0);
+            let rate_distance = rate.abs_diff(PREFERRED_SAMPLE_RATE);
+            let key = (rate_distance, range.channels() != 1, format_rank);
+            Some((key, range.with_sample_rate(cpal::SampleRate(rate))))
+        })
+        .min_by_key(|(key, _)| *key)
+        .map(|(_, config)| config);
+
+    Ok(best.unwrap_or(default))
+}
+
+/// Play a mono clip on the default output device, blocking until it ends.
+pub fn play(samples: &[f32], sample_rate: u32) -> Result<()> {
+    let device = cpal::default_host()
+        .default_output_device()
+        .ok_or_else(|| anyhow!("No default output device found"))?;
+    let config = device.default_output_config()?;
+    let rate = config.sample_rate().0;
+    let channels = config.channels() as usize;
+    let clip = resample::resample(samples, sample_rate, rate);
+    let duration_ms = clip.len() as u64 * 1000 / rate as u64;
+
+    let (done_tx, done_rx) = std::sync::mpsc::channel();
+    let stream_config: cpal::StreamConfig = config.clone().into();
+    let stream = match config.sample_format() {
+        SampleFormat::F32 => build_output_stream::<f32>(&device, &stream_config, clip, channels, done_tx),
+        SampleFormat::I16 => build_output_stream::<i16>(&device, &stream_config, clip, channels, done_tx),
+        SampleFormat::U16 => build_output_stream::<u16>(&device, &stream_config, clip, channels, done_tx),
+        SampleFormat::I32 => build_output_stream::<i32>(&device, &stream_config, clip, channels, done_tx),
+        other => Err(anyhow!("Unsupported output format {:?}", other)),
+    }?;
+    stream.play().map_err(|e| anyhow!("Failed to start playback: {}", e))?;
+
+    // Margin in case the device stalls and the end is never reached
+    done_rx.recv_timeout(std::time::Duration::from_millis(duration_ms + 1000)).ok();
+    // Let the device play out what's already in its buffer
+    std::thread::sleep(std::time::Duration::from_millis(150));
+    Ok(())
+}
+
+/// Open an output stream for sample type `T` that plays `clip` on every
+/// channel, then silence, signalling `done` once the clip has been consumed.
+fn build_output_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    clip: Vec<f32>,
+    channels: usize,
+    done: std::sync::mpsc::Sender<()>,
+) -> Result<cpal::Stream>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let mut pos = 0;
+    let stream = device.build_output_stream(
+        config,
+        move |data: &mut [T], _info: &cpal::OutputCallbackInfo| {
+            for frame in data.chunks_mut(channels) {
+                frame.fill(T::from_sample(clip.get(pos).copied().unwrap_or(0.0)));
+                pos += 1;
+            }
+            if pos >= clip.len() {
+                done.send(()).ok();
+            }
+        },
+        |err| log::error!("Playback stream error: {}", err),
+        None,
+    )?;
+    Ok(stream)
+}
+
+/// Input devices currently visible to the default host.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InputDevices {
+    pub devices: Vec<String>,
+    pub default: Option<String>,
+}
+
+/// Enumerate input devices by name. Cheap enough to poll every second or two.
+pub fn list_input_devices() -> InputDevices {
+    let host = cpal::default_host();
+    let devices = host
+        .input_devices()
+        .map(|devs| devs.filter_map(|d| d.name().ok()).collect())
+        .unwrap_or_default();
+    let default = host.default_input_device().and_then(|d| d.name().ok());
+    InputDevices { devices, default }
+}
+
+/// Get the default input device name
+pub fn get_default_input_device_name() -> String {
+    cpal::default_host()
+        .default_input_device()
+        .and_then(|d| d.name().ok())
+        .unwrap_or_else(|| "غير معروف".to_string())
+}
@@ -1,60 +1,160 @@
+use crate::api;
 use crate::audio;
+use crate::capture::{self, CaptureEvent};
 use crate::config::{self, AppConfig, HistoryEntry};
-use crate::groq;
-use crate::inject;
+use crate::continuous;
+use crate::decode;
+use crate::dictation;
+use crate::encode::UploadFormat;
+use crate::error::PipelineError;
+use crate::groq::TranscriptSegment;
+use crate::inject::{self, Injector, WindowHandle};
+use crate::meter::{self, Quality};
+use crate::pipeline::{Pipeline, PipelineState};
+use crate::preroll::PreRoll;
+use crate::subtitles::{self, SubtitleFormat};
+use crate::transcribe;
+use serde::Serialize;
 use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicBool, Ordering};
-use tauri::{AppHandle, Emitter, Manager, State};
+use tauri::{AppHandle, Emitter, Listener, Manager, State};
+use tokio_util::sync::CancellationToken;
 use anyhow::Result;
 
-#[cfg(target_os = "windows")]
-use windows_sys::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, SetForegroundWindow};
+/// How often the device watcher re-enumerates input devices.
+const DEVICE_POLL_MS: u64 = 1500;
+
+/// Default and longest duration of a microphone test.
+const MIC_TEST_SECS: u32 = 4;
+const MAX_MIC_TEST_SECS: u32 = 15;
+
+/// Silence inserted where a paused dictation resumes, so the words on
+/// either side of the pause don't run together.
+const RESUME_GAP_MS: u32 = 300;
 
 pub struct RecordingState {
     pub samples: Arc<Mutex<Vec<f32>>>,
+    /// Tells the audio thread to keep capturing. The session as a whole is
+    /// tracked by `pipeline`.
     pub is_recording: Arc<AtomicBool>,
+    /// Set while the session is paused: the audio callback drops buffers.
+    pub paused: Arc<AtomicBool>,
+    pub pipeline: Arc<Pipeline>,
     pub sample_rate: Arc<Mutex<u32>>,
     pub config: Arc<Mutex<AppConfig>>,
-    /// HWND of the editor window focused when recording started (Windows only).
-    /// Used to restore focus before text injection so Ctrl+V reaches the editor.
-    pub editor_hwnd: Arc<Mutex<isize>>,
+    /// Editor window focused when recording started (HWND on Windows, X11
+    /// window ID on Linux). Used to restore focus before text injection so
+    /// Ctrl+V reaches the editor.
+    pub editor_hwnd: Arc<Mutex<WindowHandle>>,
+    /// Backend that delivers the final text, chosen at startup.
+    pub injector: Arc<dyn Injector>,
+    /// Stream config of the current (or last) recording.
+    pub capture_info: Arc<Mutex<Option<capture::CaptureInfo>>>,
+    /// Cancels the in-flight STT/LLM requests of the current session.
+    /// Replaced with a fresh token each time a recording starts.
+    pub cancel: Arc<Mutex<CancellationToken>>,
+    /// Segmenter/injector of a hands-free session; `None` in push-to-talk mode.
+    pub continuous: Arc<Mutex<Option<continuous::Session>>>,
+    /// Idle-time pre-roll capture, when enabled. An async mutex because
+    /// opening the stream is awaited while holding it.
+    pub preroll: Arc<tokio::sync::Mutex<Option<PreRoll>>>,
 }
 
 impl RecordingState {
     pub fn new() -> Self {
         let cfg = config::load_config();
+        let injector = inject::injector_from_name(&cfg.injection_backend);
+        log::info!("Injection backend: {}", injector.name());
         Self {
             samples: Arc::new(Mutex::new(Vec::new())),
             is_recording: Arc::new(AtomicBool::new(false)),
-            sample_rate: Arc::new(Mutex::new(audio::get_default_sample_rate())),
+            paused: Arc::new(AtomicBool::new(false)),
+            pipeline: Arc::new(Pipeline::new()),
+            sample_rate: Arc::new(Mutex::new(audio::TARGET_SAMPLE_RATE)),
             config: Arc::new(Mutex::new(cfg)),
-            editor_hwnd: Arc::new(Mutex::new(0)),
+            editor_hwnd: Arc::new(Mutex::new(WindowHandle::default())),
+            injector,
+            capture_info: Arc::new(Mutex::new(None)),
+            cancel: Arc::new(Mutex::new(CancellationToken::new())),
+            continuous: Arc::new(Mutex::new(None)),
+            preroll: Arc::new(tokio::sync::Mutex::new(None)),
         }
     }
+
+    /// Name of the input device of the current (or last) recording.
+    pub(crate) fn capture_device(&self) -> Option<String> {
+        self.capture_info.lock().unwrap().as_ref().map(|i| i.device.clone())
+    }
+
+    /// Config snapshot for processing the current capture, with its
+    /// device's calibrated silence threshold applied.
+    pub(crate) fn recording_config(&self) -> AppConfig {
+        let device = self.capture_device();
+        self.config.lock().unwrap().for_device(device.as_deref())
+    }
 }
 
 #[tauri::command]
 pub async fn start_recording(
     state: State<'_, RecordingState>,
     app: AppHandle,
-) -> Result<(), String> {
-    if state.is_recording.load(Ordering::SeqCst) {
-        return Err("Already recording".to_string());
-    }
+) -> Result<(), PipelineError> {
+    state.pipeline.transition(PipelineState::Recording, &app)?;
+    let token = CancellationToken::new();
+    *state.cancel.lock().unwrap() = token.clone();
 
     // Clear previous samples
     state.samples.lock().unwrap().clear();
+    state.paused.store(false, Ordering::SeqCst);
 
-    // Update sample rate
-    let sr = audio::get_default_sample_rate();
-    *state.sample_rate.lock().unwrap() = sr;
+    // A running pre-roll simply becomes the recording: same stream, so the
+    // buffered audio and the live audio meet without a gap or an overlap
+    let handed_over = match state.preroll.lock().await.take() {
+        Some(preroll) => preroll.into_recording(
+            &app,
+            Arc::clone(&state.samples),
+            Arc::clone(&state.is_recording),
+            Arc::clone(&state.paused),
+            emit_capture_events(&app),
+        ),
+        None => None,
+    };
 
-    audio::start_capture(
-        Arc::clone(&state.samples),
-        Arc::clone(&state.is_recording),
-        app.clone(),
-    )
-    .map_err(|e| e.to_string())?;
+    let info = match handed_over {
+        Some(info) => {
+            let lead_in = state.samples.lock().unwrap().len() as u64;
+            log::info!("Recording from the pre-roll, {} ms buffered", lead_in * 1000 / audio::TARGET_SAMPLE_RATE as u64);
+            info
+        }
+        None => match capture::start_capture(
+            Arc::clone(&state.samples),
+            Arc::clone(&state.is_recording),
+            Arc::clone(&state.paused),
+            emit_capture_events(&app),
+        )
+        .await
+        {
+            Ok(info) => info,
+            Err(e) => {
+                state.pipeline.finish(PipelineState::Error, &app);
+                return Err(PipelineError::AudioDevice(e.to_string()));
+            }
+        },
+    };
+
+    // The capture thread resamples on the fly, so samples are already at the target rate
+    *state.sample_rate.lock().unwrap() = audio::TARGET_SAMPLE_RATE;
+    app.emit("audio-config", &info).ok();
+    *state.capture_info.lock().unwrap() = Some(info);
+
+    // Hands-free sessions are meant to run for as long as the user talks
+    let continuous = state.config.lock().unwrap().continuous;
+    if continuous {
+        *state.continuous.lock().unwrap() = Some(continuous::start(&app, token));
+    } else {
+        *state.continuous.lock().unwrap() = None;
+        watch_recording_limit(&app, token);
+    }
 
     app.emit("recording-started", ()).ok();
     log::info!("Recording started");
@@ -65,94 +165,334 @@ pub async fn start_recording(
 pub async fn stop_and_process(
     state: State<'_, RecordingState>,
     app: AppHandle,
-) -> Result<String, String> {
-    if !state.is_recording.load(Ordering::SeqCst) {
-        return Err("Not currently recording".to_string());
-    }
-
-    // Signal stop
-    state.is_recording.store(false, Ordering::SeqCst);
-    app.emit("recording-stopped", ()).ok();
+) -> Result<DictationResult, PipelineError> {
+    state.pipeline.transition(PipelineState::Transcribing, &app)?;
+    process_stopped(&state, &app).await
+}
 
-    // Give audio thread time to flush
-    tokio::time::sleep(tokio::time::Duration::from_millis(150)).await;
+/// Everything after the move to Transcribing: stop the capture, process it
+/// and end the session. Whoever made that move owns this part.
+async fn process_stopped(state: &RecordingState, app: &AppHandle) -> Result<DictationResult, PipelineError> {
+    // Dropping the processing future on cancel aborts any in-flight Groq request
+    let token = state.cancel.lock().unwrap().clone();
+    let session = state.continuous.lock().unwrap().take();
+    let hands_free = session.is_some();
+    let work = async {
+        stop_capture(state, app).await;
+        let quality = assess_recording(state, app);
+        let text = match session {
+            // Earlier segments are already in; wait for the rest, in order
+            Some(session) => continuous::finish(session, app).await?,
+            None => process_recording(state, app).await?,
+        };
+        Ok(DictationResult { text, quality })
+    };
+    let result = tokio::select! {
+        r = work => r,
+        _ = token.cancelled() => Err(PipelineError::Cancelled),
+    };
+    // The session is over; this also ends its recording-limit watcher
+    token.cancel();
 
-    let samples = state.samples.lock().unwrap().clone();
-    if samples.is_empty() {
-        return Err("No audio captured".to_string());
+    match result {
+        Ok(result) => {
+            if hands_free {
+                state.pipeline.finish_hands_free(app).ok();
+            } else {
+                state.pipeline.transition(PipelineState::Idle, app).ok();
+            }
+            Ok(result)
+        }
+        // `cancel_recording` already moved us to Cancelled, so every later
+        // transition (including Injecting) was rejected — nothing was pasted.
+        Err(_) if state.pipeline.get() == PipelineState::Cancelled => {
+            state.pipeline.transition(PipelineState::Idle, app).ok();
+            Err(PipelineError::Cancelled)
+        }
+        Err(e) => {
+            state.pipeline.finish(PipelineState::Error, app);
+            Err(e)
+        }
     }
+}
 
-    // Check if audio is mostly silence (RMS energy below threshold)
-    let rms = (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
-    log::info!("Audio RMS energy: {:.6}", rms);
-    if rms < 0.005 {
-        return Err("No speech detected".to_string());
-    }
+/// What a finished dictation hands back: the injected text, and how the
+/// microphone sounded (absent only for an empty recording).
+#[derive(Debug, Clone, Serialize)]
+pub struct DictationResult {
+    pub text: String,
+    pub quality: Option<Quality>,
+}
 
+/// Judge the captured audio and emit `recording-quality`, before any upload,
+/// so the warning shows even if transcription then fails. In hands-free mode
+/// only the audio after the last segment is still there to judge.
+fn assess_recording(state: &RecordingState, app: &AppHandle) -> Option<Quality> {
     let sample_rate = *state.sample_rate.lock().unwrap();
-    let config = state.config.lock().unwrap().clone();
-
-    if config.groq_api_key.is_empty() {
-        return Err("No Groq API key configured. Please add it in Settings.".to_string());
-    }
-
-    // Encode to WAV
-    app.emit("processing-status", "transcribing").ok();
-    let wav_bytes = audio::encode_to_wav(&samples, sample_rate)
-        .map_err(|e| e.to_string())?;
+    let quality = meter::assess(&state.samples.lock().unwrap(), sample_rate)?;
+    log::info!(
+        "Recording quality: {:?} (speech {:.1} dBFS, floor {:.1} dBFS, {} clipped)",
+        quality.verdict,
+        quality.speech_dbfs,
+        quality.noise_floor_dbfs,
+        quality.clipped
+    );
+    app.emit("recording-quality", quality).ok();
+    Some(quality)
+}
 
-    // STT
-    let raw_text = groq::transcribe_audio(wav_bytes, &config.groq_api_key, &config.stt_model)
-        .await
-        .map_err(|e| e.to_string())?;
+/// Run STT → refinement → injection on the stopped capture. Runs in the
+/// Transcribing state; the caller handles the terminal transition.
+async fn process_recording(state: &RecordingState, app: &AppHandle) -> Result<String, PipelineError> {
+    let samples = state.samples.lock().unwrap().clone();
+    let sample_rate = *state.sample_rate.lock().unwrap();
+    let config = state.recording_config();
+    let editor = *state.editor_hwnd.lock().unwrap();
 
-    if raw_text.trim().is_empty() {
-        return Err("No speech detected".to_string());
-    }
+    let dictation = dictation::dictate(
+        &samples,
+        sample_rate,
+        &config,
+        &transcribe::Groq,
+        &*state.injector,
+        editor,
+        |stage| {
+            // stop_and_process has already moved to Transcribing
+            if stage != PipelineState::Transcribing {
+                state.pipeline.transition(stage, app)?;
+            }
+            app.emit("processing-status", stage).ok();
+            Ok(())
+        },
+    )
+    .await?;
+    save_history(state, dictation.raw, &dictation.text, None);
 
-    // LLM refinement
-    app.emit("processing-status", "refining").ok();
-    let vocab: Vec<(String, String)> = config
-        .vocabulary
-        .iter()
-        .map(|v| (v.from.clone(), v.to.clone()))
-        .collect();
+    app.emit("processing-status", "done").ok();
+    app.emit("transcription-complete", &dictation.text).ok();
 
-    let refined = groq::refine_text(&raw_text, &config.groq_api_key, &config.system_prompt, &config.llm_model, &vocab)
-        .await
-        .map_err(|e| e.to_string())?;
+    Ok(dictation.text)
+}
 
-    // Text injection — inject_text will restore focus internally right before Ctrl+V
-    app.emit("processing-status", "injecting").ok();
-    inject::inject_text(
-        &refined,
-        #[cfg(target_os = "windows")]
-        *state.editor_hwnd.lock().unwrap(),
-    ).map_err(|e| e.to_string())?;
+/// Tell the audio thread to stop and give it time to flush.
+async fn stop_capture(state: &RecordingState, app: &AppHandle) {
+    state.is_recording.store(false, Ordering::SeqCst);
+    app.emit("recording-stopped", ()).ok();
+    tokio::time::sleep(tokio::time::Duration::from_millis(150)).await;
+}
 
-    // Save to history
+/// Add a transcript to the front of the history and persist it.
+pub(crate) fn save_history(state: &RecordingState, raw_text: String, refined: &str, source: Option<String>) -> HistoryEntry {
     let entry = HistoryEntry {
         id: uuid_simple(),
         timestamp: current_timestamp(),
         raw: raw_text,
-        refined: refined.clone(),
+        refined: refined.to_string(),
         word_count: refined.split_whitespace().count(),
+        source,
     };
 
-    {
-        let mut cfg = state.config.lock().unwrap();
-        cfg.history.insert(0, entry);
-        // Keep last 100 entries
-        if cfg.history.len() > 100 {
-            cfg.history.truncate(100);
+    let mut cfg = state.config.lock().unwrap();
+    cfg.history.insert(0, entry.clone());
+    // Keep last 100 entries
+    if cfg.history.len() > 100 {
+        cfg.history.truncate(100);
+    }
+    config::save_config(&cfg).ok();
+    entry
+}
+
+/// Outcome of `transcribe_file`.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileTranscription {
+    pub entry: HistoryEntry,
+    /// Where the subtitles were written, when requested.
+    pub subtitle_path: Option<String>,
+}
+
+/// Transcribe an existing audio or video file — e.g. a WhatsApp voice note —
+/// with the same STT and refinement as a dictation. The result goes into
+/// history (and out as `file-transcribed`); nothing is injected, and the
+/// recording pipeline is left alone, so this can run alongside a dictation.
+///
+/// With `subtitles` set to "srt" or "vtt", the file is transcribed with
+/// timestamps, refined segment by segment, and the subtitles are written
+/// next to it (`talk.mp4` → `talk.srt`).
+#[tauri::command]
+pub async fn transcribe_file(
+    state: State<'_, RecordingState>,
+    app: AppHandle,
+    path: String,
+    subtitles: Option<String>,
+) -> Result<FileTranscription, PipelineError> {
+    let subtitle_format = subtitles
+        .map(|name| {
+            SubtitleFormat::from_name(&name)
+                .ok_or_else(|| PipelineError::Config(format!("Unknown subtitle format '{}'", name)))
+        })
+        .transpose()?;
+    let path = std::path::PathBuf::from(path);
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    log::info!("Transcribing file: {}", path.display());
+
+    let decode_path = path.clone();
+    let samples = tauri::async_runtime::spawn_blocking(move || decode::decode_file(&decode_path))
+        .await
+        .map_err(|e| PipelineError::Decoding(e.to_string()))?
+        .map_err(|e| PipelineError::Decoding(e.to_string()))?;
+    if samples.is_empty() {
+        return Err(PipelineError::NoAudio);
+    }
+    log::info!(
+        "Decoded {}: {:.1}s",
+        name,
+        samples.len() as f32 / audio::TARGET_SAMPLE_RATE as f32
+    );
+
+    let config = state.config.lock().unwrap().clone();
+    let (raw_text, refined, subtitle_path) = match subtitle_format {
+        Some(format) => {
+            let segments = transcribe::transcribe_clip_timed(&samples, audio::TARGET_SAMPLE_RATE, &config).await?;
+            let refined = transcribe::refine_segments(&segments, &config).await?;
+
+            let out = path.with_extension(format.extension());
+            std::fs::write(&out, subtitles::write(&refined, format))
+                .map_err(|e| PipelineError::Output(format!("{}: {}", out.display(), e)))?;
+            log::info!("Wrote {} subtitle cues to {}", refined.len(), out.display());
+
+            let join = |segs: &[TranscriptSegment]| segs.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join(" ");
+            (join(&segments), join(&refined), Some(out.to_string_lossy().into_owned()))
+        }
+        None => {
+            let raw_text = transcribe::transcribe_clip(&samples, audio::TARGET_SAMPLE_RATE, &config).await?;
+            let refined = transcribe::refine_transcript(&raw_text, &config).await?;
+            (raw_text, refined, None)
+        }
+    };
+
+    let entry = save_history(&state, raw_text, &refined, Some(name));
+    app.emit("file-transcribed", &entry).ok();
+    Ok(FileTranscription { entry, subtitle_path })
+}
+
+/// Stop the current recording, run the pipeline and walk the overlay through
+/// done / cancelled / error. Shared by the hotkey and by automatic stops
+/// (e.g. the microphone being unplugged mid-recording).
+async fn finish_dictation(app: AppHandle) {
+    let state = app.state::<RecordingState>();
+    // An unplugged mic is both an audio error and a device-list change, so
+    // two watchers can get here for one session. The move to Transcribing
+    // is checked and made under one lock; the loser just steps aside.
+    if state.pipeline.transition(PipelineState::Transcribing, &app).is_err() {
+        log::info!("Session is already being finished");
+        return;
+    }
+    match process_stopped(&state, &app).await {
+        Ok(result) => {
+            log::info!("Injected: {}", result.text);
+            // Brief pause so user sees the "done" animation, then hide overlay
+            hide_overlay_after(&app, 800).await;
+        }
+        Err(PipelineError::Cancelled) => {
+            log::info!("Dictation cancelled — nothing injected");
+            hide_overlay_after(&app, 600).await;
+        }
+        Err(e) => {
+            log::error!("Pipeline error: {}", e);
+            app.emit("error", &e).ok();
+            // Hide overlay after error delay
+            hide_overlay_after(&app, 3200).await;
         }
-        config::save_config(&cfg).ok();
     }
+}
 
-    app.emit("processing-status", "done").ok();
-    app.emit("transcription-complete", &refined).ok();
+/// Stop a recording that runs past `max_recording_secs` — e.g. the user forgot
+/// to press the hotkey again — and emit `recording-limit` so the UI can say why.
+/// The watcher ends with the session's `cancel` token.
+fn watch_recording_limit(app: &AppHandle, session: CancellationToken) {
+    let max_secs = app.state::<RecordingState>().config.lock().unwrap().max_recording_secs;
+    if max_secs == 0 {
+        return;
+    }
 
-    Ok(refined)
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = handle.state::<RecordingState>();
+        loop {
+            tokio::select! {
+                _ = session.cancelled() => return,
+                _ = tokio::time::sleep(tokio::time::Duration::from_secs(1)) => {}
+            }
+            let limit = max_secs as usize * *state.sample_rate.lock().unwrap() as usize;
+            if state.samples.lock().unwrap().len() < limit {
+                continue;
+            }
+            // The user may be stopping at the same moment; whoever clears the
+            // capture flag first owns the stop
+            if state.is_recording.swap(false, Ordering::SeqCst) {
+                log::warn!("Recording reached the {}s limit — stopping", max_secs);
+                handle.emit("recording-limit", max_secs).ok();
+                finish_dictation(handle.clone()).await;
+            }
+            return;
+        }
+    });
+}
+
+/// Suspend the current dictation without ending it. Nothing said while
+/// paused is recorded; stopping while paused processes what was captured.
+#[tauri::command]
+pub fn pause_recording(
+    state: State<'_, RecordingState>,
+    app: AppHandle,
+) -> Result<(), PipelineError> {
+    state.pipeline.transition_from(PipelineState::Recording, PipelineState::Paused, &app)?;
+    state.paused.store(true, Ordering::SeqCst);
+    app.emit("processing-status", "paused").ok();
+    log::info!("Recording paused");
+    Ok(())
+}
+
+#[tauri::command]
+pub fn resume_recording(
+    state: State<'_, RecordingState>,
+    app: AppHandle,
+) -> Result<(), PipelineError> {
+    state.pipeline.transition_from(PipelineState::Paused, PipelineState::Recording, &app)?;
+    let gap = (audio::TARGET_SAMPLE_RATE * RESUME_GAP_MS / 1000) as usize;
+    state.samples.lock().unwrap().extend(std::iter::repeat(0.0).take(gap));
+    state.paused.store(false, Ordering::SeqCst);
+    app.emit("processing-status", "recording").ok();
+    log::info!("Recording resumed");
+    Ok(())
+}
+
+/// Abandon the current dictation. While recording, the audio is discarded;
+/// while transcribing or refining, the in-flight requests are aborted.
+/// Once injection has started it is too late and this returns an error.
+#[tauri::command]
+pub fn cancel_recording(
+    state: State<'_, RecordingState>,
+    app: AppHandle,
+) -> Result<(), PipelineError> {
+    let prev = state.pipeline.transition(PipelineState::Cancelled, &app)?;
+    state.is_recording.store(false, Ordering::SeqCst);
+    state.cancel.lock().unwrap().cancel();
+    state.continuous.lock().unwrap().take();
+    app.emit("processing-status", "cancelled").ok();
+
+    if prev.is_capturing() {
+        // No processing task to wind down — return to Idle ourselves
+        state.samples.lock().unwrap().clear();
+        app.emit("recording-stopped", ()).ok();
+        state.pipeline.transition(PipelineState::Idle, &app).ok();
+    }
+
+    log::info!("Dictation cancelled while {:?}", prev);
+    Ok(())
 }
 
 #[tauri::command]
@@ -165,18 +505,27 @@ pub fn save_config_cmd(
     state: State<'_, RecordingState>,
     app: AppHandle,
     config: AppConfig,
-) -> Result<(), String> {
+) -> Result<(), PipelineError> {
     let mut cfg = state.config.lock().unwrap();
     let hotkey_changed = cfg.hotkey != config.hotkey;
+    let api_changed = (cfg.api_enabled, cfg.api_port, &cfg.api_token)
+        != (config.api_enabled, config.api_port, &config.api_token);
     *cfg = config.clone();
     
     // Save to disk
-    let result = config::save_config(&cfg).map_err(|e| e.to_string());
+    let result = config::save_config(&cfg).map_err(|e| PipelineError::Config(e.to_string()));
     
     // Dynamically re-register hotkey if it was changed
     if hotkey_changed {
         register_hotkey(&app, &config.hotkey);
     }
+    tauri::async_runtime::spawn(sync_preroll(app.clone()));
+    drop(cfg);
+    if api_changed {
+        api::apply_config(&app);
+    }
+    // Lets open pages pick up a change made elsewhere (e.g. `set-mode` on the control socket)
+    app.emit("config-changed", ()).ok();
     
     result
 }
@@ -223,89 +572,300 @@ pub fn register_hotkey(app: &AppHandle, hotkey_str: &str) {
         let handle = app.clone();
         match app.global_shortcut().on_shortcut(shortcut, move |_app, _shortcut, event| {
             if event.state == ShortcutState::Pressed {
-                let state = handle.state::<RecordingState>();
-                let is_rec = state.is_recording.load(Ordering::SeqCst);
-                let handle2 = handle.clone();
-                
-                    if is_rec {
-                    tauri::async_runtime::spawn(async move {
-                        let state = handle2.state::<RecordingState>();
-                        match stop_and_process(state, handle2.clone()).await {
-                            Ok(text) => {
-                                log::info!("Injected: {}", text);
-                                // Brief pause so user sees the "done" animation, then hide overlay
-                                tokio::time::sleep(tokio::time::Duration::from_millis(800)).await;
-                                // Tell frontend to animate out and hide itself
-                                handle2.emit("overlay-hide", ()).ok();
-                                // Backend safety-net hide
-                                tokio::time::sleep(tokio::time::Duration::from_millis(400)).await;
-                                if let Some(overlay) = handle2.get_webview_window("overlay") {
-                                    let _ = overlay.hide();
-                                }
-                            },
-                            Err(e) => {
-                                log::error!("Pipeline error: {}", e);
-                                handle2.emit("error", &e).ok();
-                                // Hide overlay after error delay
-                                tokio::time::sleep(tokio::time::Duration::from_millis(3200)).await;
-                                handle2.emit("overlay-hide", ()).ok();
-                                tokio::time::sleep(tokio::time::Duration::from_millis(400)).await;
-                                if let Some(overlay) = handle2.get_webview_window("overlay") {
-                                    let _ = overlay.hide();
-                                }
-                            }
-                        }
-                    });
-                } else {
-                    // Capture the focused editor HWND synchronously — before any
-                    // async work or overlay display can change the foreground window.
-                    #[cfg(target_os = "windows")]
-                    {
-                        let hwnd = unsafe { GetForegroundWindow() };
-                        *state.editor_hwnd.lock().unwrap() = hwnd;
-                        log::info!("Captured editor HWND: {} (synchronous)", hwnd);
-                    }
-
-                    tauri::async_runtime::spawn(async move {
-                        // Show overlay without stealing focus from active editor
-                        if let Some(overlay) = handle2.get_webview_window("overlay") {
-                            let _ = overlay.show();
-                            let _ = overlay.set_ignore_cursor_events(true);
-                        }
-
-                        // Immediately give focus back to the editor
-                        #[cfg(target_os = "windows")]
-                        {
-                            let hwnd = *handle2.state::<RecordingState>().editor_hwnd.lock().unwrap();
-                            if hwnd != 0 {
-                                unsafe { SetForegroundWindow(hwnd); }
-                            }
-                        }
-
-                        handle2.emit("show-overlay", ()).ok();
-
-                        let state = handle2.state::<RecordingState>();
-                        if let Err(e) = start_recording(state, handle2.clone()).await {
-                            log::error!("Start recording error: {}", e);
-                            handle2.emit("error", &e).ok();
-                            tokio::time::sleep(tokio::time::Duration::from_millis(3200)).await;
-                            if let Some(overlay) = handle2.get_webview_window("overlay") {
-                                let _ = overlay.hide();
-                            }
-                        }
-                    });
+                if let Err(e) = toggle_dictation(&handle) {
+                    log::info!("Hotkey ignored: {}", e);
                 }
             }
         }) {
             Ok(_) => log::info!("Registered global hotkey: {}", normalized),
             Err(e) => {
                 log::error!("Failed to register hotkey '{}': {}", normalized, e);
-                app.emit("error", format!("فشل تسجيل الاختصار '{}'. جرب اختصاراً مختلفاً.", hotkey_str)).ok();
+                let err = PipelineError::HotkeyRegister {
+                    hotkey: hotkey_str.to_string(),
+                    reason: e.to_string(),
+                };
+                app.emit("error", &err).ok();
             }
         }
     } else {
         log::error!("Invalid hotkey format after normalization: '{}'", normalized);
-        app.emit("error", format!("صيغة الاختصار '{}' غير صحيحة. جرب مثلاً: Ctrl+Space", hotkey_str)).ok();
+        app.emit("error", PipelineError::HotkeyInvalid(hotkey_str.to_string())).ok();
+    }
+}
+
+/// What the dictation hotkey does: finish the running session, or start a
+/// new one. Also driven by the control socket.
+pub(crate) fn toggle_dictation(app: &AppHandle) -> Result<(), PipelineError> {
+    if app.state::<RecordingState>().pipeline.get().is_capturing() {
+        end_dictation(app)
+    } else {
+        begin_dictation(app)
+    }
+}
+
+/// Start dictating into the focused window. Returns once the session is
+/// under way; a failure to open the mic is emitted as `error`.
+pub(crate) fn begin_dictation(app: &AppHandle) -> Result<(), PipelineError> {
+    let state = app.state::<RecordingState>();
+    let current = state.pipeline.get();
+    if current.is_capturing() {
+        return Err(PipelineError::AlreadyRecording);
+    }
+    // Still uploading or pasting the previous dictation — don't start another
+    if current.is_busy() {
+        return Err(PipelineError::InvalidTransition {
+            from: format!("{:?}", current),
+            to: format!("{:?}", PipelineState::Recording),
+        });
+    }
+
+    // Capture the focused editor window synchronously — before any
+    // async work or overlay display can change the foreground window.
+    let editor = inject::foreground_window();
+    *state.editor_hwnd.lock().unwrap() = editor;
+    log::info!("Captured editor window: {} (synchronous)", editor.0);
+
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        // Show overlay without stealing focus from active editor
+        if let Some(overlay) = handle.get_webview_window("overlay") {
+            let _ = overlay.show();
+            let _ = overlay.set_ignore_cursor_events(true);
+        }
+
+        // Immediately give focus back to the editor
+        let editor = *handle.state::<RecordingState>().editor_hwnd.lock().unwrap();
+        inject::focus_window(editor);
+
+        handle.emit("show-overlay", ()).ok();
+
+        let state = handle.state::<RecordingState>();
+        if let Err(e) = start_recording(state, handle.clone()).await {
+            log::error!("Start recording error: {}", e);
+            handle.emit("error", &e).ok();
+            tokio::time::sleep(tokio::time::Duration::from_millis(3200)).await;
+            if let Some(overlay) = handle.get_webview_window("overlay") {
+                let _ = overlay.hide();
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Stop the running session, then transcribe, refine and inject it.
+pub(crate) fn end_dictation(app: &AppHandle) -> Result<(), PipelineError> {
+    if !app.state::<RecordingState>().pipeline.get().is_capturing() {
+        return Err(PipelineError::NotRecording);
+    }
+    tauri::async_runtime::spawn(finish_dictation(app.clone()));
+    Ok(())
+}
+
+/// Discard the running session and hide the overlay.
+pub(crate) fn cancel_dictation(app: &AppHandle) -> Result<(), PipelineError> {
+    cancel_recording(app.state::<RecordingState>(), app.clone())?;
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move { hide_overlay_after(&handle, 600).await });
+    Ok(())
+}
+
+/// Keep the pre-roll open exactly while idle (if enabled): a recording takes
+/// it over in `start_recording`, and it is reopened once the session ends.
+pub fn watch_preroll(app: &AppHandle) {
+    tauri::async_runtime::spawn(sync_preroll(app.clone()));
+    let handle = app.clone();
+    app.listen("pipeline-state", move |_event| {
+        tauri::async_runtime::spawn(sync_preroll(handle.clone()));
+    });
+}
+
+/// Open or close the pre-roll to match the config and pipeline state.
+async fn sync_preroll(app: AppHandle) {
+    let state = app.state::<RecordingState>();
+    let ms = state.config.lock().unwrap().preroll_ms;
+    let mut slot = state.preroll.lock().await;
+
+    // Close one that was disabled, died or has the wrong length. A live one is
+    // left alone even while busy: `start_recording` takes it over itself.
+    if let Some(preroll) = slot.take() {
+        if preroll.is_running() && preroll.ms() == ms {
+            *slot = Some(preroll);
+        } else {
+            preroll.stop(&app);
+        }
+    }
+
+    if ms > 0 && slot.is_none() && state.pipeline.get() == PipelineState::Idle {
+        match PreRoll::start(ms, &app).await {
+            Ok(preroll) => *slot = Some(preroll),
+            Err(e) => log::warn!("Pre-roll unavailable: {}", e),
+        }
+    }
+}
+
+/// Register the cancel and pause hotkeys only while they can do something,
+/// so a plain key like Escape isn't swallowed system-wide the rest of the time.
+pub fn watch_session_hotkeys(app: &AppHandle) {
+    let handle = app.clone();
+    app.listen("pipeline-state", move |_event| {
+        // The shortcut plugin holds its registry lock while running handlers,
+        // and a handler may be what triggered this transition — sync off-thread.
+        let handle = handle.clone();
+        tauri::async_runtime::spawn(async move {
+            let (current, cancel_hotkey, pause_hotkey) = {
+                let state = handle.state::<RecordingState>();
+                let cfg = state.config.lock().unwrap();
+                (state.pipeline.get(), cfg.cancel_hotkey.clone(), cfg.pause_hotkey.clone())
+            };
+            set_session_hotkey(&handle, &cancel_hotkey, current.is_busy(), on_cancel_hotkey);
+            set_session_hotkey(&handle, &pause_hotkey, current.is_capturing(), on_pause_hotkey);
+        });
+    });
+}
+
+fn on_cancel_hotkey(handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = cancel_dictation(&handle) {
+            log::info!("Cancel hotkey ignored: {}", e);
+        }
+    });
+}
+
+/// Toggle between paused and recording.
+fn on_pause_hotkey(handle: AppHandle) {
+    let state = handle.state::<RecordingState>();
+    let result = match state.pipeline.get() {
+        PipelineState::Paused => resume_recording(state, handle.clone()),
+        _ => pause_recording(state, handle.clone()),
+    };
+    if let Err(e) = result {
+        log::info!("Pause hotkey ignored: {}", e);
+    }
+}
+
+fn set_session_hotkey(app: &AppHandle, hotkey_str: &str, active: bool, on_press: fn(AppHandle)) {
+    use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+    use std::str::FromStr;
+
+    if hotkey_str.trim().is_empty() {
+        return;
+    }
+    let Ok(shortcut) = Shortcut::from_str(&normalize_hotkey(hotkey_str)) else {
+        log::warn!("Invalid session hotkey format: '{}'", hotkey_str);
+        return;
+    };
+
+    let shortcuts = app.global_shortcut();
+    if !active {
+        if shortcuts.is_registered(shortcut) {
+            let _ = shortcuts.unregister(shortcut);
+        }
+        return;
+    }
+    // Already active (or it clashes with the main hotkey)
+    if shortcuts.is_registered(shortcut) {
+        return;
+    }
+
+    let handle = app.clone();
+    let result = shortcuts.on_shortcut(shortcut, move |_app, _shortcut, event| {
+        if event.state == ShortcutState::Pressed {
+            on_press(handle.clone());
+        }
+    });
+    if let Err(e) = result {
+        log::error!("Failed to register session hotkey '{}': {}", hotkey_str, e);
+    }
+}
+
+/// Forward the capture thread's reports as `audio-level` (f32 RMS, 0–1),
+/// `audio-meter` and `audio-error` events.
+fn emit_capture_events(app: &AppHandle) -> impl Fn(CaptureEvent) + Send + 'static {
+    let app = app.clone();
+    move |event| match event {
+        CaptureEvent::Level(reading) => {
+            app.emit("audio-level", reading.rms).ok();
+            app.emit("audio-meter", reading).ok();
+        }
+        CaptureEvent::Failed(err) => {
+            app.emit("audio-error", &err).ok();
+        }
+    }
+}
+
+/// Handle the audio stream dying mid-recording (mic unplugged, device
+/// grabbed by another app) so the hotkey doesn't stay stuck on "stop".
+/// Whatever was captured before the failure is still transcribed.
+pub fn watch_audio_errors(app: &AppHandle) {
+    let handle = app.clone();
+    app.listen("audio-error", move |event| {
+        let detail = serde_json::from_str::<String>(event.payload()).unwrap_or_default();
+        let handle = handle.clone();
+        tauri::async_runtime::spawn(async move {
+            let state = handle.state::<RecordingState>();
+            if !state.pipeline.get().is_capturing() {
+                return;
+            }
+            // A hands-free session drains its buffer, so it may be empty
+            // even though segments were already injected
+            if !state.samples.lock().unwrap().is_empty() || state.continuous.lock().unwrap().is_some() {
+                log::warn!("Audio stream failed ({}), processing what was captured", detail);
+                finish_dictation(handle.clone()).await;
+                return;
+            }
+            state.is_recording.store(false, Ordering::SeqCst);
+            state.samples.lock().unwrap().clear();
+            handle.emit("recording-stopped", ()).ok();
+            state.pipeline.finish(PipelineState::Error, &handle);
+            handle.emit("error", PipelineError::AudioDevice(detail)).ok();
+            hide_overlay_after(&handle, 3200).await;
+        });
+    });
+}
+
+/// Poll the host's input devices, emit `input-devices-changed` whenever the
+/// list or the default changes, and stop the recording gracefully if the
+/// device it is capturing from goes away.
+pub fn watch_input_devices(app: &AppHandle) {
+    let handle = app.clone();
+    std::thread::spawn(move || {
+        let mut last = capture::list_input_devices();
+        let mut missing_polls = 0;
+        loop {
+            std::thread::sleep(std::time::Duration::from_millis(DEVICE_POLL_MS));
+            let current = capture::list_input_devices();
+
+            if current != last {
+                log::info!("Input devices changed: {:?} (default: {:?})", current.devices, current.default);
+                handle.emit("input-devices-changed", &current).ok();
+            }
+
+            let state = handle.state::<RecordingState>();
+            let active = state.capture_info.lock().unwrap().as_ref().map(|i| i.device.clone());
+            let active_missing = state.pipeline.get().is_capturing()
+                && active.is_some_and(|name| {
+                    !current.devices.contains(&name) && current.default.as_ref() != Some(&name)
+                });
+
+            // Require two polls in a row — enumeration can briefly miss a device
+            missing_polls = if active_missing { missing_polls + 1 } else { 0 };
+            if missing_polls == 2 {
+                log::warn!("Active input device disappeared, stopping recording");
+                tauri::async_runtime::spawn(finish_dictation(handle.clone()));
+            }
+
+            last = current;
+        }
+    });
+}
+
+/// Tell the overlay to animate out after `delay_ms`, then hide its window as a safety net.
+async fn hide_overlay_after(app: &AppHandle, delay_ms: u64) {
+    tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
+    app.emit("overlay-hide", ()).ok();
+    tokio::time::sleep(tokio::time::Duration::from_millis(400)).await;
+    if let Some(overlay) = app.get_webview_window("overlay") {
+        let _ = overlay.hide();
     }
 }
 
@@ -315,30 +875,145 @@ pub fn get_history(state: State<'_, RecordingState>) -> Vec<HistoryEntry> {
 }
 
 #[tauri::command]
-pub fn clear_history(state: State<'_, RecordingState>) -> Result<(), String> {
+pub fn clear_history(state: State<'_, RecordingState>) -> Result<(), PipelineError> {
     let mut cfg = state.config.lock().unwrap();
     cfg.history.clear();
-    config::save_config(&cfg).map_err(|e| e.to_string())
+    config::save_config(&cfg).map_err(|e| PipelineError::Config(e.to_string()))
 }
 
 #[tauri::command]
 pub fn delete_history_entry(
     state: State<'_, RecordingState>,
     id: String,
-) -> Result<(), String> {
+) -> Result<(), PipelineError> {
     let mut cfg = state.config.lock().unwrap();
     cfg.history.retain(|e| e.id != id);
-    config::save_config(&cfg).map_err(|e| e.to_string())
+    config::save_config(&cfg).map_err(|e| PipelineError::Config(e.to_string()))
 }
 
 #[tauri::command]
 pub fn is_recording(state: State<'_, RecordingState>) -> bool {
-    state.is_recording.load(Ordering::SeqCst)
+    state.pipeline.get().is_capturing()
+}
+
+#[tauri::command]
+pub fn get_pipeline_state(state: State<'_, RecordingState>) -> PipelineState {
+    state.pipeline.get()
+}
+
+/// Upload formats this build can encode; Opus needs the `opus` feature.
+#[tauri::command]
+pub fn get_upload_formats() -> Vec<&'static str> {
+    UploadFormat::available().into_iter().map(UploadFormat::name).collect()
 }
 
 #[tauri::command]
 pub fn get_mic_name() -> String {
-    audio::get_default_input_device_name()
+    capture::get_default_input_device_name()
+}
+
+/// Whether the pre-roll currently holds the microphone open.
+#[tauri::command]
+pub async fn get_preroll_active(state: State<'_, RecordingState>) -> Result<bool, PipelineError> {
+    Ok(state.preroll.lock().await.as_ref().is_some_and(PreRoll::is_running))
+}
+
+#[tauri::command]
+pub fn get_input_devices() -> capture::InputDevices {
+    capture::list_input_devices()
+}
+
+/// Outcome of `test_microphone`.
+#[derive(Debug, Clone, Serialize)]
+pub struct MicTest {
+    pub device: String,
+    pub quality: Quality,
+    /// Now saved for this device in `AppConfig.device_thresholds`. `None` if
+    /// the test heard no speech to set it against; the device keeps
+    /// `previous_threshold` then.
+    pub suggested_threshold: Option<f32>,
+    /// What the device used before the test.
+    pub previous_threshold: f32,
+}
+
+/// Record a few seconds from `device` (the default input if `None`), measure
+/// its speech level and noise floor, and save a silence threshold fitted to
+/// it. Speak normally for part of the test so both can be measured.
+/// With `playback`, the clip is played back once the result is saved.
+#[tauri::command]
+pub async fn test_microphone(
+    state: State<'_, RecordingState>,
+    app: AppHandle,
+    device: Option<String>,
+    seconds: Option<u32>,
+    playback: Option<bool>,
+) -> Result<MicTest, PipelineError> {
+    // Held for the whole test, so a hotkey, socket or API start meanwhile is
+    // refused rather than opening the same microphone a second time
+    state.pipeline.transition(PipelineState::Testing, &app)?;
+    let result = run_mic_test(&state, device, seconds, playback).await;
+    state.pipeline.transition(PipelineState::Idle, &app).ok();
+    result
+}
+
+async fn run_mic_test(
+    state: &RecordingState,
+    device: Option<String>,
+    seconds: Option<u32>,
+    playback: Option<bool>,
+) -> Result<MicTest, PipelineError> {
+    let secs = seconds.unwrap_or(MIC_TEST_SECS).clamp(1, MAX_MIC_TEST_SECS);
+
+    let samples = Arc::new(Mutex::new(Vec::new()));
+    let running = Arc::new(AtomicBool::new(false));
+    let info = capture::start_test_capture(device.as_deref(), Arc::clone(&samples), Arc::clone(&running))
+        .await
+        .map_err(|e| PipelineError::AudioDevice(e.to_string()))?;
+    log::info!("Testing microphone '{}' for {}s", info.device, secs);
+
+    tokio::time::sleep(tokio::time::Duration::from_secs(secs as u64)).await;
+    // Same flush delay as a normal stop
+    running.store(false, Ordering::SeqCst);
+    tokio::time::sleep(tokio::time::Duration::from_millis(150)).await;
+
+    let clip = std::mem::take(&mut *samples.lock().unwrap());
+    let quality = meter::assess(&clip, audio::TARGET_SAMPLE_RATE).ok_or(PipelineError::NoAudio)?;
+    let suggested_threshold = meter::suggest_threshold(&quality);
+
+    let previous_threshold = {
+        let mut cfg = state.config.lock().unwrap();
+        let previous = cfg.silence_threshold(Some(&info.device));
+        if let Some(threshold) = suggested_threshold {
+            cfg.device_thresholds.insert(info.device.clone(), threshold);
+            config::save_config(&cfg).map_err(|e| PipelineError::Config(e.to_string()))?;
+        }
+        previous
+    };
+    match suggested_threshold {
+        Some(threshold) => log::info!(
+            "Mic test: speech {:.1} dBFS, floor {:.1} dBFS, threshold {:.4} -> {:.4}",
+            quality.speech_dbfs,
+            quality.noise_floor_dbfs,
+            previous_threshold,
+            threshold
+        ),
+        None => log::warn!(
+            "Mic test: no speech above the {:.1} dBFS floor (speech {:.1} dBFS), keeping threshold {:.4}",
+            quality.noise_floor_dbfs,
+            quality.speech_dbfs,
+            previous_threshold
+        ),
+    }
+
+    if playback.unwrap_or(false) {
+        let played = tauri::async_runtime::spawn_blocking(move || capture::play(&clip, audio::TARGET_SAMPLE_RATE)).await;
+        // The measurement stands even if playback fails
+        if let Ok(Err(e)) = played {
+            log::warn!("Mic test playback failed: {}", e);
+        }
+    }
+
+    Ok(MicTest { device: info.device, quality, suggested_threshold, previous_threshold })
 }
 
 fn uuid_simple() -> String {
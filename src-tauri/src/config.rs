@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use anyhow::Result;
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +16,94 @@ pub struct HistoryEntry {
     pub raw: String,
     pub refined: String,
     pub word_count: usize,
+    /// File name, for entries transcribed from a file rather than dictated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+/// Voice activity detection thresholds. See `vad.rs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VadConfig {
+    /// Trim silence and collapse pauses before upload. When off, only the
+    /// whole-clip RMS is checked against `energy_threshold`.
+    pub enabled: bool,
+    /// Minimum frame RMS that can count as speech.
+    pub energy_threshold: f32,
+    /// Frames this many times above the measured noise floor count as speech.
+    pub noise_multiplier: f32,
+    /// Zero-crossing rate (crossings per sample) that marks a quiet frame as a fricative.
+    pub zcr_threshold: f32,
+    /// Less speech than this is treated as "no speech detected".
+    pub min_speech_ms: u32,
+    /// Silence kept before and after each speech segment.
+    pub padding_ms: u32,
+    /// Pauses longer than this are shortened to this length.
+    pub max_pause_ms: u32,
+    /// In continuous mode, silence this long (past the padding) ends a segment.
+    pub segment_pause_ms: u32,
+}
+
+/// Clean-up applied before VAD and upload. See `preprocess.rs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PreprocessConfig {
+    /// Cut rumble and hum below `high_pass_hz`.
+    pub high_pass: bool,
+    pub high_pass_hz: f32,
+    /// RNNoise noise suppression.
+    pub noise_suppression: bool,
+    /// Bring the speech level to `agc_target_dbfs`, boosting by at most `agc_max_gain_db`.
+    pub agc: bool,
+    pub agc_target_dbfs: f32,
+    pub agc_max_gain_db: f32,
+}
+
+/// A post-processing command. See `hooks.rs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HookConfig {
+    /// Command line, run through the platform shell.
+    pub command: String,
+    pub enabled: bool,
+    /// Send `{"text", "raw", "mode"}` on stdin instead of the bare text.
+    pub json_stdin: bool,
+    /// The hook is killed after this long, and the text passes through unchanged.
+    pub timeout_ms: u64,
+}
+
+impl Default for HookConfig {
+    fn default() -> Self {
+        Self { command: String::new(), enabled: true, json_stdin: false, timeout_ms: 2000 }
+    }
+}
+
+impl Default for PreprocessConfig {
+    fn default() -> Self {
+        Self {
+            high_pass: false,
+            high_pass_hz: 80.0,
+            noise_suppression: false,
+            agc: false,
+            agc_target_dbfs: -20.0,
+            agc_max_gain_db: 20.0,
+        }
+    }
+}
+
+impl Default for VadConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            energy_threshold: 0.005,
+            noise_multiplier: 3.0,
+            zcr_threshold: 0.3,
+            min_speech_ms: 250,
+            padding_ms: 210,
+            max_pause_ms: 600,
+            segment_pause_ms: 500,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,6 +116,72 @@ pub struct AppConfig {
     pub system_prompt: String,
     pub vocabulary: Vec<VocabularyEntry>,
     pub history: Vec<HistoryEntry>,
+    /// How refined text reaches the editor: "clipboard", "typing" or "stdout".
+    /// Read once at startup.
+    #[serde(default = "default_injection_backend")]
+    pub injection_backend: String,
+    /// Aborts the current dictation. Only registered while one is in progress.
+    #[serde(default = "default_cancel_hotkey")]
+    pub cancel_hotkey: String,
+    /// Pauses and resumes the current dictation; empty to disable.
+    /// Only registered while recording.
+    #[serde(default)]
+    pub pause_hotkey: String,
+    #[serde(default)]
+    pub vad: VadConfig,
+    #[serde(default)]
+    pub preprocess: PreprocessConfig,
+    /// Hands-free mode: the hotkey toggles listening, and each pause-delimited
+    /// segment is transcribed and injected while recording continues.
+    #[serde(default)]
+    pub continuous: bool,
+    /// Recordings are stopped automatically after this many seconds; 0 disables the limit.
+    /// Hands-free sessions have no limit.
+    #[serde(default = "default_max_recording_secs")]
+    pub max_recording_secs: u32,
+    /// Audio format sent to the STT provider: "flac", "opus" or "wav".
+    #[serde(default = "default_upload_format")]
+    pub upload_format: String,
+    /// Keep the mic open while idle and prepend this much buffered audio to
+    /// each recording. 0 keeps the mic closed until the hotkey is pressed.
+    #[serde(default)]
+    pub preroll_ms: u32,
+    /// Silence thresholds found by `test_microphone`, keyed by input device
+    /// name. Each replaces `vad.energy_threshold` when recording from that device.
+    #[serde(default)]
+    pub device_thresholds: HashMap<String, f32>,
+    /// Serve the local HTTP API on 127.0.0.1:`api_port`.
+    #[serde(default)]
+    pub api_enabled: bool,
+    #[serde(default = "default_api_port")]
+    pub api_port: u16,
+    /// Bearer token the local API requires. Generated when the API is
+    /// enabled without one; clear it to get a new one.
+    #[serde(default)]
+    pub api_token: String,
+    /// Run in order over each dictation after refinement, before injection.
+    #[serde(default)]
+    pub hooks: Vec<HookConfig>,
+}
+
+impl AppConfig {
+    /// The VAD energy threshold to use for `device`.
+    pub fn silence_threshold(&self, device: Option<&str>) -> f32 {
+        device
+            .and_then(|d| self.device_thresholds.get(d))
+            .copied()
+            .unwrap_or(self.vad.energy_threshold)
+    }
+
+    /// VAD settings for audio recorded on `device`.
+    pub fn vad_for(&self, device: Option<&str>) -> VadConfig {
+        VadConfig { energy_threshold: self.silence_threshold(device), ..self.vad.clone() }
+    }
+
+    /// A copy of this config for processing audio recorded on `device`.
+    pub fn for_device(&self, device: Option<&str>) -> AppConfig {
+        AppConfig { vad: self.vad_for(device), ..self.clone() }
+    }
 }
 
 impl Default for AppConfig {
@@ -44,10 +199,46 @@ impl Default for AppConfig {
                 VocabularyEntry { from: "سولانا".to_string(), to: "Solana".to_string() },
             ],
             history: Vec::new(),
+            injection_backend: default_injection_backend(),
+            cancel_hotkey: default_cancel_hotkey(),
+            pause_hotkey: String::new(),
+            vad: VadConfig::default(),
+            preprocess: PreprocessConfig::default(),
+            continuous: false,
+            max_recording_secs: default_max_recording_secs(),
+            upload_format: "flac".to_string(),
+            preroll_ms: 0,
+            device_thresholds: HashMap::new(),
+            api_enabled: false,
+            api_port: default_api_port(),
+            api_token: String::new(),
+            hooks: Vec::new(),
         }
     }
 }
 
+fn default_injection_backend() -> String {
+    "clipboard".to_string()
+}
+
+fn default_cancel_hotkey() -> String {
+    "Escape".to_string()
+}
+
+fn default_max_recording_secs() -> u32 {
+    15 * 60
+}
+
+/// Configs saved before `upload_format` existed keep uploading WAV; new
+/// installs start on FLAC (see `AppConfig::default`).
+fn default_upload_format() -> String {
+    "wav".to_string()
+}
+
+fn default_api_port() -> u16 {
+    47110
+}
+
 fn default_system_prompt() -> String {
     r#"أنت مساعد متخصص في تحرير النصوص المنطوقة باللهجة المصرية العامية.
 
@@ -83,12 +274,25 @@ pub fn load_config() -> AppConfig {
     }
 }
 
+/// Readable by the user alone on Unix, since it holds the Groq key and the
+/// local API token. On Windows the per-user app data folder already is.
 pub fn save_config(config: &AppConfig) -> Result<()> {
+    use std::io::Write;
+
     let path = config_path();
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)?;
     }
     let content = serde_json::to_string_pretty(config)?;
-    std::fs::write(path, content)?;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(&path)?;
+    // `mode` only applies when the file is created; tighten an older one too
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    file.write_all(content.as_bytes())?;
     Ok(())
 }
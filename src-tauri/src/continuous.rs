@@ -0,0 +1,191 @@
+//! Hands-free dictation: the stream is cut at pauses while recording carries
+//! on, and each segment is injected, in order, as soon as it is transcribed.
+
+use crate::commands::{self, RecordingState};
+use crate::dictation::{self, Outcome, Transcript};
+use crate::error::PipelineError;
+use crate::hooks;
+use crate::transcribe;
+use crate::vad;
+use serde::Serialize;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+/// How often the segmenter looks for a pause in the captured audio.
+const POLL_MS: u64 = 200;
+
+type Pending = transcribe::BoxFuture<'static, Result<Transcript, PipelineError>>;
+
+#[derive(Clone, Serialize)]
+struct SegmentEvent {
+    index: usize,
+    text: String,
+}
+
+#[derive(Clone, Serialize)]
+struct SegmentFailed {
+    index: usize,
+    error: PipelineError,
+}
+
+/// Segments are uploaded concurrently, but their tasks go through a FIFO
+/// queue that `dictation::inject_in_order` drains in order.
+struct Segmenter {
+    next_index: usize,
+    /// Dropped by `finish` to tell the injector no more segments are coming.
+    queue: Option<mpsc::UnboundedSender<Pending>>,
+    cancel: CancellationToken,
+}
+
+/// A running continuous session, owned by `RecordingState`.
+pub struct Session {
+    segmenter: Arc<Mutex<Segmenter>>,
+    injector: JoinHandle<Outcome>,
+}
+
+/// Start segmenting the capture that `start_recording` just opened.
+pub fn start(app: &AppHandle, cancel: CancellationToken) -> Session {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let segmenter = Arc::new(Mutex::new(Segmenter {
+        next_index: 0,
+        queue: Some(tx),
+        cancel: cancel.clone(),
+    }));
+
+    tauri::async_runtime::spawn(segment_loop(app.clone(), Arc::clone(&segmenter)));
+    let injector = tauri::async_runtime::spawn(inject_loop(app.clone(), rx, cancel));
+    log::info!("Continuous dictation started");
+    Session { segmenter, injector }
+}
+
+/// Queue whatever was said after the last pause as the final segment, then
+/// wait until every segment has been injected. Capture must already be stopped.
+/// Returns all injected text, space-joined.
+pub async fn finish(session: Session, app: &AppHandle) -> Result<String, PipelineError> {
+    {
+        let state = app.state::<RecordingState>();
+        let mut seg = session.segmenter.lock().unwrap();
+        let tail = std::mem::take(&mut *state.samples.lock().unwrap());
+        if !tail.is_empty() {
+            enqueue(app, &mut seg, tail);
+        }
+        // Closing the queue lets the injector finish once it's drained
+        seg.queue = None;
+    }
+
+    let outcome = session.injector.await.unwrap_or_default();
+    log::info!("Continuous dictation finished: {} segment(s) injected", outcome.injected.len());
+    if outcome.injected.is_empty() {
+        return Err(outcome.last_error.unwrap_or(PipelineError::NoSpeech));
+    }
+
+    let text = outcome.injected.join(" ");
+    app.emit("processing-status", "done").ok();
+    app.emit("transcription-complete", &text).ok();
+    Ok(text)
+}
+
+/// Poll the growing sample buffer and cut a segment at each pause. Audio
+/// before a cut is drained, so the buffer only ever holds the current
+/// utterance however long the session runs.
+async fn segment_loop(app: AppHandle, segmenter: Arc<Mutex<Segmenter>>) {
+    let state = app.state::<RecordingState>();
+    loop {
+        tokio::time::sleep(tokio::time::Duration::from_millis(POLL_MS)).await;
+        if !state.is_recording.load(Ordering::SeqCst) {
+            break;
+        }
+
+        let sample_rate = *state.sample_rate.lock().unwrap();
+        let device = state.capture_device();
+        let vad_config = state.config.lock().unwrap().vad_for(device.as_deref());
+
+        // Held across the cut so `finish` never sees a segment that was
+        // queued but not yet drained
+        let mut seg = segmenter.lock().unwrap();
+        if seg.queue.is_none() || seg.cancel.is_cancelled() {
+            break;
+        }
+        let pending = state.samples.lock().unwrap().clone();
+
+        if let Some(cut) = vad::next_cut(&pending, sample_rate, &vad_config) {
+            if cut.speech {
+                enqueue(&app, &mut seg, pending[..cut.end].to_vec());
+            }
+            // Only the capture thread appends meanwhile, so the front is
+            // unchanged unless a cancel just cleared the buffer
+            let mut samples = state.samples.lock().unwrap();
+            let drained = cut.end.min(samples.len());
+            samples.drain(..drained);
+        }
+    }
+}
+
+/// Start transcribing a segment and append its task to the injection queue.
+fn enqueue(app: &AppHandle, seg: &mut Segmenter, clip: Vec<f32>) {
+    let index = seg.next_index;
+    seg.next_index += 1;
+
+    let state = app.state::<RecordingState>();
+    let sample_rate = *state.sample_rate.lock().unwrap();
+    let config = state.recording_config();
+    let cancel = seg.cancel.clone();
+    log::info!(
+        "Segment {}: {:.2}s queued",
+        index,
+        clip.len() as f32 / sample_rate as f32
+    );
+
+    let task = tauri::async_runtime::spawn(async move {
+        let work = async {
+            let raw = transcribe::transcribe_clip(&clip, sample_rate, &config).await?;
+            let refined = transcribe::refine_transcript(&raw, &config).await?;
+            let refined = hooks::run_hooks(&refined, &raw, "hands-free", &config.hooks).await;
+            Ok(Transcript { raw, refined })
+        };
+        // Dropping `work` on cancel aborts the in-flight Groq request
+        tokio::select! {
+            r = work => r,
+            _ = cancel.cancelled() => Err(PipelineError::Cancelled),
+        }
+    });
+
+    let pending: Pending = Box::pin(async move {
+        task.await.unwrap_or_else(|e| {
+            // A panicked task has nothing to inject; skip it like a silent segment
+            log::error!("Segment {} task failed: {}", index, e);
+            Err(PipelineError::NoSpeech)
+        })
+    });
+    if let Some(queue) = &seg.queue {
+        queue.send(pending).ok();
+    }
+}
+
+/// Inject segments in spoken order, saving each to history as it lands.
+async fn inject_loop(
+    app: AppHandle,
+    queue: mpsc::UnboundedReceiver<Pending>,
+    cancel: CancellationToken,
+) -> Outcome {
+    let state = app.state::<RecordingState>();
+    let injector = Arc::clone(&state.injector);
+    let editor = *state.editor_hwnd.lock().unwrap();
+
+    dictation::inject_in_order(queue, &*injector, editor, cancel, |index, result| match result {
+        Ok(t) => {
+            commands::save_history(&state, t.raw.clone(), &t.refined, None);
+            let event = SegmentEvent { index, text: t.refined.clone() };
+            app.emit("segment-complete", event).ok();
+        }
+        Err(e) => {
+            let event = SegmentFailed { index, error: e.clone() };
+            app.emit("segment-failed", event).ok();
+        }
+    })
+    .await
+}
@@ -0,0 +1,223 @@
+//! Decoding of audio and video files for `transcribe_file` and for uploads
+//! to the local API.
+
+use crate::audio::TARGET_SAMPLE_RATE;
+use crate::resample;
+use anyhow::{anyhow, Context, Result};
+use std::io::{Cursor, Read, SeekFrom};
+use std::path::Path;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::{MediaSource, MediaSourceStream};
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+/// Largest Opus frame (120 ms) at the target rate.
+#[cfg(feature = "opus")]
+const MAX_OPUS_FRAME: usize = TARGET_SAMPLE_RATE as usize * 120 / 1000;
+
+/// Opus pre-skip is counted at 48 kHz.
+#[cfg(feature = "opus")]
+const OPUS_GRANULE_RATE: u32 = 48_000;
+
+/// Decode the first audio track of `path` to mono at `TARGET_SAMPLE_RATE`.
+///
+/// Symphonia handles WAV, MP3, AAC/M4A (including the audio track of MP4
+/// video), Ogg/Vorbis and FLAC in pure Rust. It has no Opus decoder, so
+/// Ogg/Opus — what WhatsApp voice notes are — goes through libopus and is
+/// only supported in builds with the `opus` feature.
+pub fn decode_file(path: &Path) -> Result<Vec<f32>> {
+    let file = std::fs::File::open(path).with_context(|| format!("Cannot open {}", path.display()))?;
+    decode(file, path.extension().and_then(|e| e.to_str()))
+}
+
+/// Decode an in-memory file. `extension` (e.g. "mp3") is only a hint: the
+/// container is detected from the data.
+pub fn decode_bytes<T>(data: T, extension: Option<&str>) -> Result<Vec<f32>>
+where
+    T: AsRef<[u8]> + Send + Sync + 'static,
+{
+    decode(Cursor::new(data), extension)
+}
+
+fn decode<R: MediaSource + 'static>(mut source: R, extension: Option<&str>) -> Result<Vec<f32>> {
+    if is_ogg_opus(&mut source)? {
+        #[cfg(feature = "opus")]
+        return decode_ogg_opus(source);
+        #[cfg(not(feature = "opus"))]
+        return Err(anyhow!("Ogg/Opus needs a build with the `opus` feature"));
+    }
+
+    let source = MediaSourceStream::new(Box::new(source), Default::default());
+    let mut hint = Hint::new();
+    if let Some(ext) = extension {
+        hint.with_extension(ext);
+    }
+
+    let probed = symphonia::default::get_probe()
+        .format(&hint, source, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(|e| anyhow!("Unsupported or corrupt file: {}", e))?;
+    let mut format = probed.format;
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| anyhow!("No audio track found"))?;
+
+    let track_id = track.id;
+    let mut sample_rate = track.codec_params.sample_rate;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| anyhow!("Unsupported codec: {}", e))?;
+
+    let mut mono = Vec::new();
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            // End of stream
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(SymphoniaError::ResetRequired) => break,
+            Err(e) => return Err(anyhow!("Read failed: {}", e)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A damaged packet costs a few milliseconds, not the whole file
+            Err(SymphoniaError::DecodeError(e)) => {
+                log::warn!("Skipping undecodable packet: {}", e);
+                continue;
+            }
+            Err(e) => return Err(anyhow!("Decode failed: {}", e)),
+        };
+
+        let spec = *decoded.spec();
+        sample_rate = Some(spec.rate);
+        let channels = spec.channels.count().max(1);
+        let mut buf = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        buf.copy_interleaved_ref(decoded);
+        mono.extend(
+            buf.samples()
+                .chunks(channels)
+                .map(|frame| frame.iter().sum::<f32>() / channels as f32),
+        );
+    }
+
+    let sample_rate = sample_rate.ok_or_else(|| anyhow!("Unknown sample rate"))?;
+    Ok(resample::resample(&mono, sample_rate, TARGET_SAMPLE_RATE))
+}
+
+/// Whether the stream opens with an Ogg page carrying an `OpusHead` packet
+/// (RFC 7845). Leaves `source` rewound to the start.
+fn is_ogg_opus(source: &mut impl MediaSource) -> Result<bool> {
+    // 27-byte page header, up to 255 lacing values, then the packet
+    let mut head = Vec::with_capacity(27 + 255 + 8);
+    source.by_ref().take(27).read_to_end(&mut head)?;
+    let opus = if head.len() == 27 && head.starts_with(b"OggS") {
+        let lacing = head[26] as u64;
+        source.by_ref().take(lacing + 8).read_to_end(&mut head)?;
+        head[27..].get(lacing as usize..).is_some_and(|p| p == b"OpusHead")
+    } else {
+        false
+    };
+    source.seek(SeekFrom::Start(0))?;
+    Ok(opus)
+}
+
+/// Ogg/Opus through libopus, decoded straight to mono at the target rate —
+/// libopus resamples and downmixes internally.
+#[cfg(feature = "opus")]
+fn decode_ogg_opus(source: impl Read + std::io::Seek) -> Result<Vec<f32>> {
+    use audiopus::coder::Decoder;
+    use audiopus::packet::Packet;
+    use audiopus::{Channels, MutSignals, SampleRate};
+
+    let mut reader = ogg::reading::PacketReader::new(std::io::BufReader::new(source));
+
+    let head = reader
+        .read_packet()?
+        .ok_or_else(|| anyhow!("Empty Ogg stream"))?;
+    if !head.data.starts_with(b"OpusHead") || head.data.len() < 19 {
+        return Err(anyhow!("Missing OpusHead"));
+    }
+    let pre_skip = u16::from_le_bytes([head.data[10], head.data[11]]) as usize;
+    let serial = head.stream_serial();
+
+    let mut decoder = Decoder::new(SampleRate::Hz16000, Channels::Mono)?;
+    let mut out = Vec::new();
+    let mut frame = vec![0.0f32; MAX_OPUS_FRAME];
+    let mut tags_seen = false;
+    while let Some(packet) = reader.read_packet()? {
+        // Only the first logical stream; the packet after the head is OpusTags
+        if packet.stream_serial() != serial {
+            continue;
+        }
+        if !tags_seen {
+            tags_seen = true;
+            continue;
+        }
+        let n = decoder.decode_float(
+            Some(Packet::try_from(&packet.data[..])?),
+            MutSignals::try_from(&mut frame[..])?,
+            false,
+        )?;
+        out.extend_from_slice(&frame[..n]);
+    }
+
+    // Drop the encoder delay the header asks decoders to skip
+    let skip = pre_skip * TARGET_SAMPLE_RATE as usize / OPUS_GRANULE_RATE as usize;
+    out.drain(..skip.min(out.len()));
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The first page of an Ogg/Opus stream: page header, one lacing value,
+    /// then the start of the `OpusHead` packet.
+    fn opus_first_page() -> Vec<u8> {
+        let mut page = b"OggS".to_vec();
+        page.extend_from_slice(&[0, 2]);
+        page.extend_from_slice(&[0; 20]);
+        page.push(1);
+        page.push(19);
+        page.extend_from_slice(b"OpusHead");
+        page.extend_from_slice(&[1, 1, 0x38, 0x01, 0x80, 0x3e, 0, 0, 0, 0, 0]);
+        page
+    }
+
+    #[test]
+    fn sniffs_ogg_opus_and_rewinds() {
+        let mut source = Cursor::new(opus_first_page());
+        assert!(is_ogg_opus(&mut source).unwrap());
+        assert_eq!(source.position(), 0);
+
+        let wav = crate::audio::encode_to_wav(&[0.0; 160], TARGET_SAMPLE_RATE).unwrap();
+        let mut source = Cursor::new(wav);
+        assert!(!is_ogg_opus(&mut source).unwrap());
+        assert_eq!(source.position(), 0);
+
+        assert!(!is_ogg_opus(&mut Cursor::new(b"OggS".to_vec())).unwrap());
+    }
+
+    #[test]
+    fn decodes_wav() {
+        let clip: Vec<f32> = (0..1600).map(|i| (i as f32 * 0.05).sin() * 0.5).collect();
+        let wav = crate::audio::encode_to_wav(&clip, TARGET_SAMPLE_RATE).unwrap();
+        let decoded = decode_bytes(wav, Some("wav")).unwrap();
+        assert_eq!(decoded.len(), clip.len());
+        assert!(decoded.iter().zip(&clip).all(|(a, b)| (a - b).abs() < 1e-3));
+    }
+
+    #[cfg(not(feature = "opus"))]
+    #[test]
+    fn opus_without_the_feature_says_so() {
+        let err = decode_bytes(opus_first_page(), Some("ogg")).unwrap_err();
+        assert!(err.to_string().contains("`opus` feature"), "{}", err);
+    }
+}
@@ -0,0 +1,364 @@
+//! Dictation from captured audio to injected text, push-to-talk or hands-free.
+//! Kept free of Tauri so the whole path can run against stubs in tests.
+
+use crate::config::AppConfig;
+use crate::error::PipelineError;
+use crate::hooks;
+use crate::inject::{Injector, WindowHandle};
+use crate::pipeline::PipelineState;
+use crate::transcribe::Provider;
+use std::future::Future;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+/// What a finished dictation produced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dictation {
+    pub raw: String,
+    pub text: String,
+}
+
+/// Transcribe, refine, run the hooks and inject. `on_stage` is told of each
+/// step (`Transcribing`, `Refining`, `Injecting`) before it starts, and
+/// stops the dictation if it returns an error.
+pub async fn dictate(
+    samples: &[f32],
+    sample_rate: u32,
+    config: &AppConfig,
+    provider: &dyn Provider,
+    injector: &dyn Injector,
+    editor: WindowHandle,
+    mut on_stage: impl FnMut(PipelineState) -> Result<(), PipelineError> + Send,
+) -> Result<Dictation, PipelineError> {
+    if samples.is_empty() {
+        return Err(PipelineError::NoAudio);
+    }
+
+    on_stage(PipelineState::Transcribing)?;
+    let raw = provider.transcribe(samples, sample_rate, config).await?;
+
+    on_stage(PipelineState::Refining)?;
+    let refined = provider.refine(&raw, config).await?;
+    let text = hooks::run_hooks(&refined, &raw, "push-to-talk", &config.hooks).await;
+
+    // The injector restores focus internally right before pasting
+    on_stage(PipelineState::Injecting)?;
+    injector
+        .inject(&text, editor)
+        .map_err(|e| PipelineError::Injection(e.to_string()))?;
+
+    Ok(Dictation { raw, text })
+}
+
+/// One hands-free segment, transcribed, refined and through the hooks.
+pub struct Transcript {
+    pub raw: String,
+    pub refined: String,
+}
+
+/// What `inject_in_order` hands back once the queue is drained.
+#[derive(Default)]
+pub struct Outcome {
+    pub injected: Vec<String>,
+    pub last_error: Option<PipelineError>,
+}
+
+/// Await segment tasks strictly in queue order and inject each result, so a
+/// short segment that comes back early still waits for the one spoken
+/// before it. A failed segment is passed to `on_segment` and skipped; the
+/// session keeps going. Ends when the queue closes or `cancel` fires.
+pub async fn inject_in_order<F>(
+    mut queue: mpsc::UnboundedReceiver<F>,
+    injector: &dyn Injector,
+    editor: WindowHandle,
+    cancel: CancellationToken,
+    mut on_segment: impl FnMut(usize, Result<&Transcript, &PipelineError>),
+) -> Outcome
+where
+    F: Future<Output = Result<Transcript, PipelineError>>,
+{
+    let mut outcome = Outcome::default();
+    let mut index = 0;
+
+    while let Some(pending) = queue.recv().await {
+        let result = pending.await;
+
+        // Checked right before each paste: nothing is injected after a cancel
+        if cancel.is_cancelled() {
+            break;
+        }
+
+        let result = result.and_then(|t| {
+            injector
+                .inject(&t.refined, editor)
+                .map_err(|e| PipelineError::Injection(e.to_string()))?;
+            Ok(t)
+        });
+
+        match result {
+            Ok(t) => {
+                log::info!("Segment {} injected: {}", index, t.refined);
+                on_segment(index, Ok(&t));
+                outcome.injected.push(t.refined);
+            }
+            Err(PipelineError::NoSpeech) => log::info!("Segment {}: no speech, skipped", index),
+            Err(e) => {
+                log::error!("Segment {} failed: {}", index, e);
+                on_segment(index, Err(&e));
+                outcome.last_error = Some(e);
+            }
+        }
+        index += 1;
+    }
+
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inject::RecordingInjector;
+    use crate::transcribe::BoxFuture;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    /// Answers with canned text instead of calling Groq, or fails like a
+    /// dropped connection when there is none.
+    struct StubProvider {
+        transcript: Option<String>,
+        calls: AtomicUsize,
+    }
+
+    impl StubProvider {
+        fn new(transcript: Option<&str>) -> Self {
+            Self {
+                transcript: transcript.map(str::to_string),
+                calls: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    impl Provider for StubProvider {
+        fn transcribe<'a>(
+            &'a self,
+            _samples: &'a [f32],
+            _sample_rate: u32,
+            _config: &'a AppConfig,
+        ) -> BoxFuture<'a, Result<String, PipelineError>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let transcript = self
+                .transcript
+                .clone()
+                .ok_or_else(|| PipelineError::Network("offline".into()));
+            Box::pin(async move { transcript })
+        }
+
+        fn refine<'a>(
+            &'a self,
+            raw_text: &'a str,
+            _config: &'a AppConfig,
+        ) -> BoxFuture<'a, Result<String, PipelineError>> {
+            Box::pin(async move { Ok(format!("{}.", raw_text)) })
+        }
+    }
+
+    const CLIP: [f32; 4] = [0.0, 0.1, -0.1, 0.0];
+
+    #[tokio::test]
+    async fn injects_refined_text_and_reports_each_stage() {
+        let provider = StubProvider::new(Some("مرحبا"));
+        let injector = RecordingInjector::new();
+        let mut stages = Vec::new();
+
+        let dictation = dictate(
+            &CLIP,
+            16_000,
+            &AppConfig::default(),
+            &provider,
+            &injector,
+            WindowHandle::default(),
+            |s| {
+                stages.push(s);
+                Ok(())
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            dictation,
+            Dictation {
+                raw: "مرحبا".into(),
+                text: "مرحبا.".into()
+            }
+        );
+        assert_eq!(injector.injected(), vec!["مرحبا."]);
+        assert_eq!(
+            stages,
+            [
+                PipelineState::Transcribing,
+                PipelineState::Refining,
+                PipelineState::Injecting
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn provider_failure_injects_nothing() {
+        let provider = StubProvider::new(None);
+        let injector = RecordingInjector::new();
+
+        let result = dictate(
+            &CLIP,
+            16_000,
+            &AppConfig::default(),
+            &provider,
+            &injector,
+            WindowHandle::default(),
+            |_| Ok(()),
+        )
+        .await;
+
+        assert!(matches!(result, Err(PipelineError::Network(_))));
+        assert!(injector.injected().is_empty());
+    }
+
+    #[tokio::test]
+    async fn rejected_stage_stops_before_injecting() {
+        let provider = StubProvider::new(Some("مرحبا"));
+        let injector = RecordingInjector::new();
+
+        let result = dictate(
+            &CLIP,
+            16_000,
+            &AppConfig::default(),
+            &provider,
+            &injector,
+            WindowHandle::default(),
+            |s| match s {
+                PipelineState::Injecting => Err(PipelineError::Cancelled),
+                _ => Ok(()),
+            },
+        )
+        .await;
+
+        assert!(matches!(result, Err(PipelineError::Cancelled)));
+        assert!(injector.injected().is_empty());
+    }
+
+    #[tokio::test]
+    async fn empty_capture_never_reaches_the_provider() {
+        let provider = StubProvider::new(Some("مرحبا"));
+        let injector = RecordingInjector::new();
+
+        let result = dictate(
+            &[],
+            16_000,
+            &AppConfig::default(),
+            &provider,
+            &injector,
+            WindowHandle::default(),
+            |_| Ok(()),
+        )
+        .await;
+
+        assert!(matches!(result, Err(PipelineError::NoAudio)));
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 0);
+        assert!(injector.injected().is_empty());
+    }
+
+    /// Transcribe `text` on its own task, taking `delay_ms`, and note when
+    /// it is done in `finished`.
+    fn segment(
+        text: &'static str,
+        delay_ms: u64,
+        finished: &Arc<Mutex<Vec<&'static str>>>,
+    ) -> impl Future<Output = Result<Transcript, PipelineError>> {
+        let finished = Arc::clone(finished);
+        let task = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+            finished.lock().unwrap().push(text);
+            match text {
+                "" => Err(PipelineError::Network("offline".into())),
+                _ => Ok(Transcript {
+                    raw: text.into(),
+                    refined: text.into(),
+                }),
+            }
+        });
+        async move { task.await.unwrap() }
+    }
+
+    #[tokio::test]
+    async fn segments_are_injected_in_spoken_order() {
+        let finished = Arc::new(Mutex::new(Vec::new()));
+        let (tx, rx) = mpsc::unbounded_channel();
+        tx.send(segment("first", 150, &finished)).unwrap();
+        tx.send(segment("second", 0, &finished)).unwrap();
+        tx.send(segment("third", 50, &finished)).unwrap();
+        drop(tx);
+        let injector = RecordingInjector::new();
+
+        let outcome = inject_in_order(
+            rx,
+            &injector,
+            WindowHandle::default(),
+            CancellationToken::new(),
+            |_, _| {},
+        )
+        .await;
+
+        assert_eq!(*finished.lock().unwrap(), ["second", "third", "first"]);
+        assert_eq!(injector.injected(), ["first", "second", "third"]);
+        assert_eq!(outcome.injected, ["first", "second", "third"]);
+    }
+
+    #[tokio::test]
+    async fn failed_segment_is_reported_and_skipped() {
+        let finished = Arc::new(Mutex::new(Vec::new()));
+        let (tx, rx) = mpsc::unbounded_channel();
+        tx.send(segment("first", 0, &finished)).unwrap();
+        tx.send(segment("", 0, &finished)).unwrap();
+        tx.send(segment("third", 0, &finished)).unwrap();
+        drop(tx);
+        let injector = RecordingInjector::new();
+        let mut failed = Vec::new();
+
+        let outcome = inject_in_order(
+            rx,
+            &injector,
+            WindowHandle::default(),
+            CancellationToken::new(),
+            |i, r| {
+                if r.is_err() {
+                    failed.push(i);
+                }
+            },
+        )
+        .await;
+
+        assert_eq!(injector.injected(), ["first", "third"]);
+        assert_eq!(failed, [1]);
+        assert!(matches!(
+            outcome.last_error,
+            Some(PipelineError::Network(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn nothing_is_injected_after_cancel() {
+        let finished = Arc::new(Mutex::new(Vec::new()));
+        let (tx, rx) = mpsc::unbounded_channel();
+        tx.send(segment("first", 0, &finished)).unwrap();
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let injector = RecordingInjector::new();
+
+        let outcome =
+            inject_in_order(rx, &injector, WindowHandle::default(), cancel, |_, _| {}).await;
+
+        assert!(injector.injected().is_empty());
+        assert!(outcome.injected.is_empty());
+    }
+}
@@ -0,0 +1,176 @@
+//! Upload encoders: WAV, FLAC, and Ogg/Opus with the `opus` feature.
+//! `benches/encode.rs` compares their encode time and payload size.
+
+use crate::audio::{self, TARGET_SAMPLE_RATE};
+use crate::resample;
+use anyhow::{anyhow, Result};
+
+/// Opus bitrate for speech; well above where Whisper accuracy starts to drop.
+#[cfg(feature = "opus")]
+const OPUS_BITRATE: i32 = 24_000;
+
+/// 20 ms Opus frames at the target rate.
+#[cfg(feature = "opus")]
+const OPUS_FRAME: usize = TARGET_SAMPLE_RATE as usize / 50;
+
+/// Ogg/Opus granule positions always count 48 kHz samples.
+#[cfg(feature = "opus")]
+const OPUS_GRANULE_RATE: u64 = 48_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UploadFormat {
+    Wav,
+    Flac,
+    Opus,
+}
+
+impl UploadFormat {
+    /// Parse `AppConfig.upload_format`. Unknown names, and Opus in a build
+    /// without the `opus` feature, fall back to FLAC.
+    pub fn from_name(name: &str) -> Self {
+        match Self::available().into_iter().find(|f| f.name() == name) {
+            Some(format) => format,
+            None => {
+                log::warn!("Upload format '{}' not available, using flac", name);
+                Self::Flac
+            }
+        }
+    }
+
+    /// The formats this build can encode.
+    pub fn available() -> Vec<Self> {
+        let mut formats = vec![Self::Flac, Self::Wav];
+        if cfg!(feature = "opus") {
+            formats.insert(1, Self::Opus);
+        }
+        formats
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Wav => "wav",
+            Self::Flac => "flac",
+            Self::Opus => "opus",
+        }
+    }
+
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            Self::Wav => "audio/wav",
+            Self::Flac => "audio/flac",
+            Self::Opus => "audio/ogg",
+        }
+    }
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Self::Wav => "audio.wav",
+            Self::Flac => "audio.flac",
+            Self::Opus => "audio.ogg",
+        }
+    }
+}
+
+/// Encode `samples` for upload, resampling to `TARGET_SAMPLE_RATE` first.
+pub fn encode(samples: &[f32], sample_rate: u32, format: UploadFormat) -> Result<Vec<u8>> {
+    match format {
+        UploadFormat::Wav => audio::encode_to_wav(samples, sample_rate),
+        UploadFormat::Flac => encode_flac(&resample::resample(samples, sample_rate, TARGET_SAMPLE_RATE)),
+        #[cfg(feature = "opus")]
+        UploadFormat::Opus => encode_ogg_opus(&resample::resample(samples, sample_rate, TARGET_SAMPLE_RATE)),
+        #[cfg(not(feature = "opus"))]
+        UploadFormat::Opus => Err(anyhow!("Built without Opus support (the `opus` feature)")),
+    }
+}
+
+/// 16-bit mono FLAC at the target rate.
+fn encode_flac(samples: &[f32]) -> Result<Vec<u8>> {
+    use flacenc::component::BitRepr;
+    use flacenc::error::Verify;
+
+    let pcm: Vec<i32> = samples.iter().map(|&s| to_i16(s) as i32).collect();
+    let config = flacenc::config::Encoder::default()
+        .into_verified()
+        .map_err(|(_, e)| anyhow!("FLAC config: {:?}", e))?;
+    let source = flacenc::source::MemSource::from_samples(&pcm, 1, 16, TARGET_SAMPLE_RATE as usize);
+    let stream = flacenc::encode_with_fixed_block_size(&config, source, config.block_size)
+        .map_err(|e| anyhow!("FLAC encode: {:?}", e))?;
+
+    let mut sink = flacenc::bitsink::ByteSink::new();
+    stream.write(&mut sink).map_err(|e| anyhow!("FLAC write: {:?}", e))?;
+    Ok(sink.into_inner())
+}
+
+/// Mono Opus in an Ogg container (RFC 7845) at the target rate.
+#[cfg(feature = "opus")]
+fn encode_ogg_opus(samples: &[f32]) -> Result<Vec<u8>> {
+    use audiopus::coder::Encoder;
+    use audiopus::{Application, Bitrate, Channels, SampleRate};
+    use ogg::writing::{PacketWriteEndInfo, PacketWriter};
+
+    let mut encoder = Encoder::new(SampleRate::Hz16000, Channels::Mono, Application::Voip)?;
+    encoder.set_bitrate(Bitrate::BitsPerSecond(OPUS_BITRATE))?;
+
+    let scale = OPUS_GRANULE_RATE / TARGET_SAMPLE_RATE as u64;
+    let lookahead = encoder.lookahead()? as usize;
+    let pre_skip = lookahead as u64 * scale;
+    // Final granule position: decoders trim the encoder delay and the padding
+    let end_granule = pre_skip + samples.len() as u64 * scale;
+
+    let serial = 0x656b_7462; // "ektb"
+    let mut writer = PacketWriter::new(Vec::new());
+    writer.write_packet(opus_head(pre_skip as u16), serial, PacketWriteEndInfo::EndPage, 0)?;
+    writer.write_packet(opus_tags(), serial, PacketWriteEndInfo::EndPage, 0)?;
+
+    // Pad with silence so the delayed tail makes it out of the encoder
+    let mut input = samples.to_vec();
+    let frames = ((samples.len() + lookahead + OPUS_FRAME - 1) / OPUS_FRAME).max(1);
+    input.resize(frames * OPUS_FRAME, 0.0);
+
+    let mut packet = [0u8; 4000];
+    for (i, frame) in input.chunks(OPUS_FRAME).enumerate() {
+        let len = encoder.encode_float(frame, &mut packet[..])?;
+        let last = i + 1 == frames;
+        let granule = if last {
+            end_granule
+        } else {
+            (i as u64 + 1) * OPUS_FRAME as u64 * scale
+        };
+        let end = if last {
+            PacketWriteEndInfo::EndStream
+        } else {
+            PacketWriteEndInfo::NormalPacket
+        };
+        writer.write_packet(packet[..len].to_vec(), serial, end, granule)?;
+    }
+
+    Ok(writer.into_inner())
+}
+
+#[cfg(feature = "opus")]
+fn opus_head(pre_skip: u16) -> Vec<u8> {
+    let mut head = Vec::with_capacity(19);
+    head.extend_from_slice(b"OpusHead");
+    head.push(1); // version
+    head.push(1); // channels
+    head.extend_from_slice(&pre_skip.to_le_bytes());
+    head.extend_from_slice(&TARGET_SAMPLE_RATE.to_le_bytes());
+    head.extend_from_slice(&0i16.to_le_bytes()); // output gain
+    head.push(0); // mapping family: mono/stereo
+    head
+}
+
+#[cfg(feature = "opus")]
+fn opus_tags() -> Vec<u8> {
+    let vendor = b"ektb";
+    let mut tags = Vec::new();
+    tags.extend_from_slice(b"OpusTags");
+    tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    tags.extend_from_slice(vendor);
+    tags.extend_from_slice(&0u32.to_le_bytes()); // no user comments
+    tags
+}
+
+fn to_i16(s: f32) -> i16 {
+    (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+}
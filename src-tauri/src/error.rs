@@ -0,0 +1,178 @@
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::fmt;
+
+/// Error returned by the Groq API with a non-success HTTP status.
+#[derive(Debug)]
+pub struct ApiError {
+    pub stage: &'static str,
+    pub status: u16,
+    pub body: String,
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Groq {} error {}: {}", self.stage, self.status, self.body)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// Everything that can go wrong between the hotkey press and the paste.
+///
+/// Serialized for the frontend as `{ code, retryable, message, details }` so
+/// the UI can localize by `code` instead of parsing English text.
+#[derive(Debug, Clone)]
+pub enum PipelineError {
+    NoApiKey,
+    AlreadyRecording,
+    NotRecording,
+    NoAudio,
+    NoSpeech,
+    AudioDevice(String),
+    Unauthorized(String),
+    RateLimited(String),
+    Network(String),
+    Provider { status: u16, body: String },
+    Encoding(String),
+    Decoding(String),
+    Output(String),
+    Injection(String),
+    Config(String),
+    HotkeyInvalid(String),
+    HotkeyRegister { hotkey: String, reason: String },
+    InvalidTransition { from: String, to: String },
+    Cancelled,
+    /// Malformed request from the control socket or the local API.
+    BadRequest(String),
+    /// Local API request without the configured token.
+    BadToken,
+}
+
+impl PipelineError {
+    /// Stable machine-readable code. Never rename these — the UI keys on them.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NoApiKey => "no_api_key",
+            Self::AlreadyRecording => "already_recording",
+            Self::NotRecording => "not_recording",
+            Self::NoAudio => "no_audio",
+            Self::NoSpeech => "no_speech",
+            Self::AudioDevice(_) => "audio_device",
+            Self::Unauthorized(_) => "unauthorized",
+            Self::RateLimited(_) => "rate_limited",
+            Self::Network(_) => "network",
+            Self::Provider { .. } => "provider",
+            Self::Encoding(_) => "encoding",
+            Self::Decoding(_) => "decoding",
+            Self::Output(_) => "output",
+            Self::Injection(_) => "injection",
+            Self::Config(_) => "config",
+            Self::HotkeyInvalid(_) => "hotkey_invalid",
+            Self::HotkeyRegister { .. } => "hotkey_register",
+            Self::InvalidTransition { .. } => "invalid_transition",
+            Self::Cancelled => "cancelled",
+            Self::BadRequest(_) => "bad_request",
+            Self::BadToken => "bad_token",
+        }
+    }
+
+    /// Whether simply trying again (without the user changing anything) may succeed.
+    pub fn retryable(&self) -> bool {
+        match self {
+            Self::NoSpeech
+            | Self::RateLimited(_)
+            | Self::Network(_)
+            | Self::Injection(_)
+            | Self::InvalidTransition { .. } => true,
+            Self::Provider { status, .. } => *status >= 500,
+            _ => false,
+        }
+    }
+
+    pub fn details(&self) -> Option<String> {
+        match self {
+            Self::AudioDevice(d)
+            | Self::Unauthorized(d)
+            | Self::RateLimited(d)
+            | Self::Network(d)
+            | Self::Encoding(d)
+            | Self::Decoding(d)
+            | Self::Output(d)
+            | Self::Injection(d)
+            | Self::Config(d)
+            | Self::HotkeyInvalid(d)
+            | Self::BadRequest(d) => Some(d.clone()),
+            Self::Provider { status, body } => Some(format!("HTTP {}: {}", status, body)),
+            // Just the shortcut, which the UI puts in its localized message;
+            // the reason is in `message`
+            Self::HotkeyRegister { hotkey, .. } => Some(hotkey.clone()),
+            Self::InvalidTransition { from, to } => Some(format!("{} → {}", from, to)),
+            _ => None,
+        }
+    }
+
+    /// Classify an error coming out of `groq::*` by HTTP status or transport failure.
+    pub fn from_provider(err: anyhow::Error) -> Self {
+        if let Some(api) = err.downcast_ref::<ApiError>() {
+            return match api.status {
+                401 | 403 => Self::Unauthorized(api.body.clone()),
+                429 => Self::RateLimited(api.body.clone()),
+                status => Self::Provider { status, body: api.body.clone() },
+            };
+        }
+        if let Some(e) = err.downcast_ref::<reqwest::Error>() {
+            // A reply that isn't the JSON we expect is the provider's fault, not the network's
+            if e.is_decode() {
+                return Self::Provider { status: e.status().map_or(0, |s| s.as_u16()), body: err.to_string() };
+            }
+            return Self::Network(err.to_string());
+        }
+        Self::Provider { status: 0, body: err.to_string() }
+    }
+}
+
+impl fmt::Display for PipelineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoApiKey => write!(f, "No Groq API key configured. Please add it in Settings."),
+            Self::AlreadyRecording => write!(f, "Already recording"),
+            Self::NotRecording => write!(f, "Not currently recording"),
+            Self::NoAudio => write!(f, "No audio captured"),
+            Self::NoSpeech => write!(f, "No speech detected"),
+            Self::AudioDevice(d) => write!(f, "Audio device error: {}", d),
+            Self::Unauthorized(_) => write!(f, "Groq API key was rejected"),
+            Self::RateLimited(_) => write!(f, "Groq rate limit reached"),
+            Self::Network(d) => write!(f, "Network error: {}", d),
+            Self::Provider { status, body } => write!(f, "Groq error {}: {}", status, body),
+            Self::Encoding(d) => write!(f, "Audio encoding failed: {}", d),
+            Self::Decoding(d) => write!(f, "Could not read audio file: {}", d),
+            Self::Output(d) => write!(f, "Could not write output file: {}", d),
+            Self::Injection(d) => write!(f, "Text injection failed: {}", d),
+            Self::Config(d) => write!(f, "Config error: {}", d),
+            Self::HotkeyInvalid(h) => write!(f, "Invalid hotkey format: '{}'", h),
+            Self::HotkeyRegister { hotkey, reason } => {
+                write!(f, "Failed to register hotkey '{}': {}", hotkey, reason)
+            }
+            Self::InvalidTransition { from, to } => {
+                write!(f, "Busy: cannot go from {} to {}", from, to)
+            }
+            Self::Cancelled => write!(f, "Cancelled"),
+            Self::BadRequest(d) => write!(f, "Bad request: {}", d),
+            Self::BadToken => write!(f, "Missing or wrong API token"),
+        }
+    }
+}
+
+impl std::error::Error for PipelineError {}
+
+impl Serialize for PipelineError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("PipelineError", 4)?;
+        s.serialize_field("code", self.code())?;
+        s.serialize_field("retryable", &self.retryable())?;
+        s.serialize_field("message", &self.to_string())?;
+        s.serialize_field("details", &self.details())?;
+        s.end()
+    }
+}
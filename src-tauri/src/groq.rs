@@ -1,12 +1,35 @@
+use crate::encode::UploadFormat;
+use crate::error::ApiError;
 use anyhow::{anyhow, Result};
 use reqwest::multipart;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 const GROQ_BASE: &str = "https://api.groq.com/openai/v1";
 
+/// Largest audio file the transcription endpoint accepts (free tier).
+pub const MAX_UPLOAD_BYTES: usize = 25 * 1024 * 1024;
+
+/// One timed segment of a `verbose_json` transcription. Times are in
+/// seconds from the start of the uploaded clip.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptSegment {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+}
+
 #[derive(Deserialize)]
 struct TranscriptionResponse {
     text: String,
+    #[serde(default)]
+    segments: Vec<TranscriptSegment>,
+}
+
+/// What the STT endpoint returned. `segments` is only filled in when
+/// timing was requested.
+pub struct Transcription {
+    pub text: String,
+    pub segments: Vec<TranscriptSegment>,
 }
 
 #[derive(Deserialize)]
@@ -24,20 +47,27 @@ struct ChatMessage {
     content: String,
 }
 
-/// Transcribe WAV audio bytes using Groq Whisper
-pub async fn transcribe_audio(wav_bytes: Vec<u8>, api_key: &str, stt_model: &str) -> Result<String> {
+/// Transcribe encoded audio using Groq Whisper. With `timed`, asks for
+/// `verbose_json` and keeps the segment timestamps.
+pub async fn transcribe_audio(
+    audio: Vec<u8>,
+    format: UploadFormat,
+    api_key: &str,
+    stt_model: &str,
+    timed: bool,
+) -> Result<Transcription> {
     let client = reqwest::Client::new();
 
-    let file_part = multipart::Part::bytes(wav_bytes)
-        .file_name("audio.wav")
-        .mime_str("audio/wav")?;
+    let file_part = multipart::Part::bytes(audio)
+        .file_name(format.file_name())
+        .mime_str(format.mime_type())?;
 
     let model = stt_model.to_string();
     let form = multipart::Form::new()
         .part("file", file_part)
         .text("model", model)
         .text("language", "ar")
-        .text("response_format", "text");
+        .text("response_format", if timed { "verbose_json" } else { "text" });
 
     let resp = client
         .post(format!("{}/audio/transcriptions", GROQ_BASE))
@@ -49,13 +79,20 @@ pub async fn transcribe_audio(wav_bytes: Vec<u8>, api_key: &str, stt_model: &str
     if !resp.status().is_success() {
         let status = resp.status();
         let body = resp.text().await.unwrap_or_default();
-        return Err(anyhow!("Groq STT error {}: {}", status, body));
+        return Err(ApiError { stage: "STT", status: status.as_u16(), body }.into());
+    }
+
+    if timed {
+        let body: TranscriptionResponse = resp.json().await?;
+        let text = body.text.trim().to_string();
+        log::info!("STT raw transcription ({} segments): {}", body.segments.len(), text);
+        return Ok(Transcription { text, segments: body.segments });
     }
 
     // Whisper with response_format=text returns plain text, not JSON
     let text = resp.text().await?.trim().to_string();
     log::info!("STT raw transcription: {}", text);
-    Ok(text)
+    Ok(Transcription { text, segments: Vec::new() })
 }
 
 /// Refine raw transcription using Groq LLM — preserves Egyptian dialect
@@ -117,7 +154,7 @@ pub async fn refine_text(
     if !resp.status().is_success() {
         let status = resp.status();
         let body = resp.text().await.unwrap_or_default();
-        return Err(anyhow!("Groq LLM error {}: {}", status, body));
+        return Err(ApiError { stage: "LLM", status: status.as_u16(), body }.into());
     }
 
     let chat_resp: ChatResponse = resp.json().await?;
@@ -0,0 +1,248 @@
+//! Post-processing hooks: user commands that rewrite a dictation after
+//! refinement and before it is injected — e.g. turning Jira keys into links.
+
+use crate::config::HookConfig;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Run every enabled hook over `text`. `raw` is the unrefined transcript and
+/// `mode` is "push-to-talk" or "hands-free".
+///
+/// Each hook runs through the platform shell (`sh -c` / `cmd /C`) and gets
+/// the previous hook's output on stdin, so plain filters like `sed` work;
+/// `EKTB_TEXT`, `EKTB_RAW_TEXT` and `EKTB_MODE` are set as well. With
+/// `json_stdin`, stdin carries `{"text": …, "raw": …, "mode": …}` instead.
+/// Whatever the hook prints on stdout (minus the trailing newline) becomes
+/// the new text.
+///
+/// A hook that fails to start, exits non-zero, times out or prints nothing
+/// is skipped with a warning: the text passes through unchanged, so a broken
+/// script never costs the user a dictation.
+pub async fn run_hooks(text: &str, raw: &str, mode: &str, hooks: &[HookConfig]) -> String {
+    let mut text = text.to_string();
+    for hook in hooks
+        .iter()
+        .filter(|h| h.enabled && !h.command.trim().is_empty())
+    {
+        match run_hook(hook, &text, raw, mode).await {
+            Ok(output) => text = output,
+            Err(reason) => log::warn!("Hook '{}' skipped: {}", hook.command, reason),
+        }
+    }
+    text
+}
+
+/// Run with `tokio::process` rather than `tauri-plugin-shell`, whose child
+/// handle cannot close stdin — a hook reading stdin to the end would never
+/// see EOF.
+async fn run_hook(hook: &HookConfig, text: &str, raw: &str, mode: &str) -> Result<String, String> {
+    let mut command = shell_command(&hook.command);
+    command
+        .env("EKTB_TEXT", text)
+        .env("EKTB_RAW_TEXT", raw)
+        .env("EKTB_MODE", mode)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // A hook that times out is dropped mid-wait; make sure it dies too
+        .kill_on_drop(true);
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("could not start: {}", e))?;
+    let pid = child.id();
+
+    let input = if hook.json_stdin {
+        serde_json::json!({ "text": text, "raw": raw, "mode": mode }).to_string()
+    } else {
+        text.to_string()
+    };
+    let stdin = child.stdin.take();
+    // Fed alongside the wait, so a hook that prints before reading can't deadlock
+    let feed = async move {
+        if let Some(mut stdin) = stdin {
+            // A hook that ignores stdin may exit before reading it
+            let _ = stdin.write_all(input.as_bytes()).await;
+        }
+    };
+    let run = async { tokio::join!(feed, child.wait_with_output()).1 };
+
+    let timeout = tokio::time::Duration::from_millis(hook.timeout_ms);
+    let output = match tokio::time::timeout(timeout, run).await {
+        Ok(output) => output.map_err(|e| e.to_string())?,
+        Err(_) => {
+            // The shell is killed on drop, but not what it started
+            if let Some(pid) = pid {
+                kill_tree(pid);
+            }
+            return Err(format!("timed out after {} ms", hook.timeout_ms));
+        }
+    };
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(match stderr.trim() {
+            "" => output.status.to_string(),
+            stderr => format!("{}: {}", output.status, stderr),
+        });
+    }
+    let stdout = String::from_utf8(output.stdout).map_err(|_| "output is not UTF-8".to_string())?;
+    let replacement = stdout.trim_end_matches(&['\r', '\n'][..]);
+    if replacement.trim().is_empty() {
+        return Err("printed nothing".into());
+    }
+    Ok(replacement.to_string())
+}
+
+/// In a process group of its own, so `kill_tree` reaches everything the
+/// hook starts.
+#[cfg(not(target_os = "windows"))]
+fn shell_command(line: &str) -> Command {
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(line).process_group(0);
+    command
+}
+
+#[cfg(not(target_os = "windows"))]
+fn kill_tree(pid: u32) {
+    // The shell leads its group, so the group id is its pid
+    unsafe {
+        libc::killpg(pid as libc::pid_t, libc::SIGKILL);
+    }
+}
+
+/// No console window flashing up on every dictation.
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+#[cfg(target_os = "windows")]
+fn shell_command(line: &str) -> Command {
+    let mut command = Command::new("cmd");
+    // Passed as-is: cmd.exe doesn't follow the quoting rules `arg` applies
+    command
+        .arg("/C")
+        .raw_arg(line)
+        .creation_flags(CREATE_NO_WINDOW);
+    command
+}
+
+#[cfg(target_os = "windows")]
+fn kill_tree(pid: u32) {
+    use std::os::windows::process::CommandExt;
+
+    let killed = std::process::Command::new("taskkill")
+        .args(["/T", "/F", "/PID", &pid.to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .creation_flags(CREATE_NO_WINDOW)
+        .status();
+    if let Err(e) = killed {
+        log::warn!("Could not kill hook process tree {}: {}", pid, e);
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn hook(command: &str) -> HookConfig {
+        HookConfig {
+            command: command.into(),
+            timeout_ms: 500,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn stdout_replaces_the_text() {
+        let hooks = [hook("sed s/PROJ-1/PROJ-42/"), hook("tr a-z A-Z")];
+        let text = run_hooks("fix PROJ-1", "raw", "push-to-talk", &hooks).await;
+        assert_eq!(text, "FIX PROJ-42");
+    }
+
+    #[tokio::test]
+    async fn context_comes_in_env_and_json() {
+        let env = run_hooks(
+            "t",
+            "r",
+            "hands-free",
+            &[hook(
+                r#"printf '%s|%s|%s' "$EKTB_TEXT" "$EKTB_RAW_TEXT" "$EKTB_MODE""#,
+            )],
+        )
+        .await;
+        assert_eq!(env, "t|r|hands-free");
+
+        let json = HookConfig {
+            json_stdin: true,
+            ..hook("cat")
+        };
+        let stdin = run_hooks("t", "r", "hands-free", &[json]).await;
+        let value: serde_json::Value = serde_json::from_str(&stdin).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({ "text": "t", "raw": "r", "mode": "hands-free" })
+        );
+    }
+
+    #[tokio::test]
+    async fn failing_hooks_pass_the_text_through() {
+        for command in ["echo oops >&2; exit 3", "true", "/nonexistent/hook"] {
+            let text = run_hooks("keep me", "raw", "push-to-talk", &[hook(command)]).await;
+            assert_eq!(text, "keep me", "after `{}`", command);
+        }
+        // A later hook still runs on the unchanged text
+        let text = run_hooks(
+            "keep me",
+            "raw",
+            "push-to-talk",
+            &[hook("exit 1"), hook("tr a-z A-Z")],
+        )
+        .await;
+        assert_eq!(text, "KEEP ME");
+    }
+
+    #[tokio::test]
+    async fn timeout_passes_the_text_through() {
+        let started = Instant::now();
+        let text = run_hooks(
+            "keep me",
+            "raw",
+            "push-to-talk",
+            &[hook("sleep 10; echo late")],
+        )
+        .await;
+        assert_eq!(text, "keep me");
+        assert!(
+            started.elapsed() < Duration::from_secs(3),
+            "took {:?}",
+            started.elapsed()
+        );
+    }
+
+    #[tokio::test]
+    async fn timeout_kills_what_the_hook_started() {
+        let pidfile = std::env::temp_dir().join(format!("ektb-hook-test-{}", std::process::id()));
+        let command = format!("sleep 30 & echo $! > {}; wait", pidfile.display());
+        run_hooks("text", "raw", "push-to-talk", &[hook(&command)]).await;
+
+        let pid: libc::pid_t = std::fs::read_to_string(&pidfile)
+            .unwrap()
+            .trim()
+            .parse()
+            .unwrap();
+        std::fs::remove_file(&pidfile).ok();
+        // Reaped by init shortly after the kill
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while unsafe { libc::kill(pid, 0) } == 0 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert_ne!(
+            unsafe { libc::kill(pid, 0) },
+            0,
+            "grandchild {} survived the timeout",
+            pid
+        );
+    }
+}
@@ -1,20 +1,200 @@
 use anyhow::Result;
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "app")]
 use std::thread;
+#[cfg(feature = "app")]
 use std::time::Duration;
 
 #[cfg(target_os = "windows")]
 use windows_sys::Win32::UI::WindowsAndMessaging::{
     GetForegroundWindow, SetForegroundWindow, ShowWindow, SW_RESTORE,
 };
-#[cfg(target_os = "windows")]
+#[cfg(all(target_os = "windows", feature = "app"))]
 use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
     SendInput, INPUT, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_KEYUP, VK_CONTROL, VK_V,
 };
 
-pub fn inject_text(
-    text: &str,
-    #[cfg(target_os = "windows")] editor_hwnd: isize,
-) -> Result<()> {
+/// Native handle of the window that had focus when recording started —
+/// an HWND on Windows, an X11 window ID on Linux. `0` means "unknown".
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WindowHandle(pub isize);
+
+impl WindowHandle {
+    pub fn is_none(self) -> bool {
+        self.0 == 0
+    }
+}
+
+/// Capture the currently focused top-level window.
+pub fn foreground_window() -> WindowHandle {
+    #[cfg(target_os = "windows")]
+    {
+        WindowHandle(unsafe { GetForegroundWindow() })
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        WindowHandle(x11::active_window().map(|w| w as isize).unwrap_or(0))
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    {
+        WindowHandle::default()
+    }
+}
+
+/// Bring `handle` back to the foreground. Returns false if the platform
+/// refused or the handle is unknown.
+pub fn focus_window(handle: WindowHandle) -> bool {
+    if handle.is_none() {
+        return false;
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        unsafe { SetForegroundWindow(handle.0) != 0 }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        x11::activate_window(handle.0 as u32)
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    {
+        false
+    }
+}
+
+/// A way of delivering the final text to the user's editor. Selected once at
+/// startup from `AppConfig.injection_backend`.
+pub trait Injector: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn inject(&self, text: &str, editor: WindowHandle) -> Result<()>;
+}
+
+#[cfg(feature = "app")]
+/// Put the text on the clipboard and send Ctrl+V (default).
+pub struct ClipboardInjector;
+
+#[cfg(feature = "app")]
+impl Injector for ClipboardInjector {
+    fn name(&self) -> &'static str {
+        "clipboard"
+    }
+
+    fn inject(&self, text: &str, editor: WindowHandle) -> Result<()> {
+        inject_text(text, editor)
+    }
+}
+
+#[cfg(feature = "app")]
+/// Type the text key by key. Slower, but leaves the clipboard untouched and
+/// works in apps that block paste.
+pub struct TypingInjector;
+
+#[cfg(feature = "app")]
+impl Injector for TypingInjector {
+    fn name(&self) -> &'static str {
+        "typing"
+    }
+
+    fn inject(&self, text: &str, editor: WindowHandle) -> Result<()> {
+        restore_focus(editor);
+        use enigo::{Enigo, KeyboardControllable};
+        Enigo::new().key_sequence(text);
+        Ok(())
+    }
+}
+
+/// Print the text to stdout instead of touching any window. Useful for
+/// headless runs and debugging.
+pub struct StdoutInjector;
+
+impl Injector for StdoutInjector {
+    fn name(&self) -> &'static str {
+        "stdout"
+    }
+
+    fn inject(&self, text: &str, _editor: WindowHandle) -> Result<()> {
+        println!("{}", text);
+        Ok(())
+    }
+}
+
+/// Records every injected string in memory so tests can assert on exactly
+/// what would have been typed, without a display server. Not selectable
+/// from the config.
+#[derive(Clone, Default)]
+pub struct RecordingInjector {
+    injected: Arc<Mutex<Vec<String>>>,
+}
+
+impl RecordingInjector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All strings injected so far, oldest first.
+    pub fn injected(&self) -> Vec<String> {
+        self.injected.lock().unwrap().clone()
+    }
+}
+
+impl Injector for RecordingInjector {
+    fn name(&self) -> &'static str {
+        "recorder"
+    }
+
+    fn inject(&self, text: &str, _editor: WindowHandle) -> Result<()> {
+        self.injected.lock().unwrap().push(text.to_string());
+        Ok(())
+    }
+}
+
+#[cfg(feature = "app")]
+/// Build the injector named in the config, falling back to clipboard paste.
+pub fn injector_from_name(name: &str) -> Arc<dyn Injector> {
+    match name {
+        "clipboard" => Arc::new(ClipboardInjector),
+        "typing" => Arc::new(TypingInjector),
+        "stdout" => Arc::new(StdoutInjector),
+        other => {
+            log::warn!("Unknown injection backend '{}', using clipboard", other);
+            Arc::new(ClipboardInjector)
+        }
+    }
+}
+
+#[cfg(feature = "app")]
+/// Re-focus the editor window and give the window manager a moment to settle.
+fn restore_focus(editor: WindowHandle) {
+    if editor.is_none() {
+        log::warn!("No editor window saved, injecting into current foreground window");
+        thread::sleep(Duration::from_millis(300));
+        return;
+    }
+
+    #[cfg(target_os = "windows")]
+    unsafe {
+        ShowWindow(editor.0, SW_RESTORE);
+    }
+    focus_window(editor);
+    thread::sleep(Duration::from_millis(150));
+
+    let fg = foreground_window();
+    if fg != editor {
+        log::warn!(
+            "Focus not on target (expected {}, got {}), retrying...",
+            editor.0, fg.0
+        );
+        focus_window(editor);
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+#[cfg(feature = "app")]
+pub fn inject_text(text: &str, editor: WindowHandle) -> Result<()> {
     let mut clipboard = arboard::Clipboard::new()
         .map_err(|e| anyhow::anyhow!("Failed to open clipboard: {}", e))?;
     clipboard.set_text(text)
@@ -23,7 +203,7 @@ pub fn inject_text(
 
     #[cfg(target_os = "windows")]
     {
-        let hwnd = editor_hwnd;
+        let hwnd = editor.0;
         let join_handle = thread::spawn(move || -> Result<()> {
             unsafe {
                 if hwnd != 0 {
@@ -59,7 +239,8 @@ pub fn inject_text(
 
     #[cfg(not(target_os = "windows"))]
     {
-        thread::sleep(Duration::from_millis(300));
+        restore_focus(editor);
+
         use enigo::{Enigo, KeyboardControllable};
         let mut enigo = Enigo::new();
         enigo.key_down(enigo::Key::Control);
@@ -72,6 +253,60 @@ pub fn inject_text(
     Ok(())
 }
 
+/// EWMH helpers for X11 (and XWayland) sessions. On pure Wayland the
+/// connection fails and we fall back to pasting into whatever has focus.
+#[cfg(target_os = "linux")]
+mod x11 {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{AtomEnum, ClientMessageEvent, ConnectionExt, EventMask};
+    use x11rb::rust_connection::RustConnection;
+
+    fn connect() -> Option<(RustConnection, u32, u32)> {
+        let (conn, screen_num) = x11rb::connect(None)
+            .map_err(|e| log::warn!("X11 connection failed: {}", e))
+            .ok()?;
+        let root = conn.setup().roots[screen_num].root;
+        let atom = conn
+            .intern_atom(false, b"_NET_ACTIVE_WINDOW")
+            .ok()?
+            .reply()
+            .ok()?
+            .atom;
+        Some((conn, root, atom))
+    }
+
+    /// Read `_NET_ACTIVE_WINDOW` from the root window.
+    pub fn active_window() -> Option<u32> {
+        let (conn, root, atom) = connect()?;
+        let reply = conn
+            .get_property(false, root, atom, AtomEnum::WINDOW, 0, 1)
+            .ok()?
+            .reply()
+            .ok()?;
+        let window = reply.value32()?.next()?;
+        (window != 0).then_some(window)
+    }
+
+    /// Ask the window manager to activate `window`. Source indication 2
+    /// ("pager") makes most WMs honour the request without focus-stealing checks.
+    pub fn activate_window(window: u32) -> bool {
+        let Some((conn, root, atom)) = connect() else {
+            return false;
+        };
+        let event = ClientMessageEvent::new(32, window, atom, [2, x11rb::CURRENT_TIME, 0, 0, 0]);
+        let sent = conn
+            .send_event(
+                false,
+                root,
+                EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+                event,
+            )
+            .is_ok();
+        sent && conn.flush().is_ok()
+    }
+}
+
+#[cfg(feature = "app")]
 #[cfg(target_os = "windows")]
 unsafe fn send_ctrl_v() {
     use std::mem;
@@ -0,0 +1,296 @@
+//! Local control socket, so the running app can be scripted — e.g. from a
+//! tiling window manager's key bindings instead of the global hotkey.
+
+use crate::commands::{self, RecordingState};
+use crate::config;
+use crate::error::PipelineError;
+use serde_json::{json, Value};
+use std::io;
+use tauri::{AppHandle, Emitter, Listener, Manager};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::broadcast;
+
+/// Events streamed to subscribers. The audio meters (20 a second) are left out.
+pub(crate) const FORWARDED_EVENTS: &[&str] = &[
+    "pipeline-state",
+    "recording-started",
+    "recording-stopped",
+    "recording-quality",
+    "recording-limit",
+    "processing-status",
+    "segment-complete",
+    "segment-failed",
+    "transcription-complete",
+    "file-transcribed",
+    "error",
+];
+
+/// Events a subscriber may fall behind by before it starts missing some.
+const EVENT_BACKLOG: usize = 64;
+
+/// Open the control socket. Failure (e.g. a second instance already owns
+/// it) is logged, not fatal: the app works without it.
+pub fn start(app: &AppHandle, events: broadcast::Sender<String>) {
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = listen(handle, events).await {
+            log::error!("Control socket unavailable: {}", e);
+        }
+    });
+}
+
+/// Re-broadcast `FORWARDED_EVENTS` as ready-to-send JSON lines. Shared by
+/// the control socket and the local API's WebSocket.
+pub(crate) fn forward_events(app: &AppHandle) -> broadcast::Sender<String> {
+    let (tx, _) = broadcast::channel(EVENT_BACKLOG);
+    for &name in FORWARDED_EVENTS {
+        let tx = tx.clone();
+        app.listen(name, move |event| {
+            let payload: Value = serde_json::from_str(event.payload()).unwrap_or(Value::Null);
+            // Nobody subscribed is fine
+            let _ = tx.send(json!({ "type": "event", "event": name, "payload": payload }).to_string());
+        });
+    }
+    tx
+}
+
+/// `$XDG_RUNTIME_DIR/ektb/control.sock`, or `<config dir>/ektb/run/control.sock`
+/// where there is no runtime dir. Only its owner can connect: the socket is
+/// 0600 inside a 0700 directory.
+///
+/// ```text
+/// echo toggle | socat - UNIX-CONNECT:$XDG_RUNTIME_DIR/ektb/control.sock
+/// ```
+#[cfg(not(target_os = "windows"))]
+pub fn socket_path() -> std::path::PathBuf {
+    match dirs::runtime_dir() {
+        Some(dir) => dir.join("ektb").join("control.sock"),
+        None => dirs::config_dir()
+            .unwrap_or_else(|| std::path::PathBuf::from("."))
+            .join("ektb")
+            .join("run")
+            .join("control.sock"),
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+async fn listen(app: AppHandle, events: broadcast::Sender<String>) -> io::Result<()> {
+    use std::fs::{DirBuilder, Permissions};
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    let path = socket_path();
+    let dir = path.parent().expect("socket path has a parent");
+    DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+    // `create` leaves an existing directory's mode alone
+    std::fs::set_permissions(dir, Permissions::from_mode(0o700))?;
+
+    if std::os::unix::net::UnixStream::connect(&path).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AddrInUse,
+            format!("{} is held by another instance", path.display()),
+        ));
+    }
+    // Left behind by a previous run
+    let _ = std::fs::remove_file(&path);
+    let listener = tokio::net::UnixListener::bind(&path)?;
+    std::fs::set_permissions(&path, Permissions::from_mode(0o600))?;
+    log::info!("Control socket listening on {}", path.display());
+
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tauri::async_runtime::spawn(serve(stream, app.clone(), events.clone()));
+            }
+            Err(e) => {
+                log::warn!("Control socket accept failed: {}", e);
+                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+            }
+        }
+    }
+}
+
+/// `\\.\pipe\ektb-<user>`. Its ACL admits its owner alone, and remote
+/// clients are rejected.
+#[cfg(target_os = "windows")]
+pub fn pipe_name() -> String {
+    format!(r"\\.\pipe\ektb-{}", std::env::var("USERNAME").unwrap_or_default())
+}
+
+/// Full access for the pipe's owner and SYSTEM, nothing inherited.
+#[cfg(target_os = "windows")]
+const PIPE_SDDL: &str = "D:P(A;;GA;;;SY)(A;;GA;;;OW)";
+
+#[cfg(target_os = "windows")]
+async fn listen(app: AppHandle, events: broadcast::Sender<String>) -> io::Result<()> {
+    let name = pipe_name();
+    // The first instance fails if someone else already created the pipe
+    let mut server = create_pipe(&name, true)?;
+    log::info!("Control pipe listening on {}", name);
+
+    loop {
+        // A client that gives up mid-connect leaves this instance unusable;
+        // start over with a fresh one
+        if let Err(e) = server.connect().await {
+            log::warn!("Control pipe connect failed: {}", e);
+            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+            server = create_pipe(&name, false)?;
+            continue;
+        }
+        let client = std::mem::replace(&mut server, create_pipe(&name, false)?);
+        tauri::async_runtime::spawn(serve(client, app.clone(), events.clone()));
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn create_pipe(name: &str, first: bool) -> io::Result<tokio::net::windows::named_pipe::NamedPipeServer> {
+    use std::sync::OnceLock;
+    use tokio::net::windows::named_pipe::ServerOptions;
+    use windows_sys::Win32::Security::SECURITY_ATTRIBUTES;
+
+    // Built once and kept for the life of the process
+    static DESCRIPTOR: OnceLock<usize> = OnceLock::new();
+    let descriptor = match DESCRIPTOR.get() {
+        Some(&descriptor) => descriptor,
+        None => {
+            let descriptor = owner_only_descriptor()?;
+            *DESCRIPTOR.get_or_init(|| descriptor)
+        }
+    };
+
+    let mut attributes = SECURITY_ATTRIBUTES {
+        nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+        lpSecurityDescriptor: descriptor as *mut _,
+        bInheritHandle: 0,
+    };
+    unsafe {
+        ServerOptions::new()
+            .first_pipe_instance(first)
+            .reject_remote_clients(true)
+            .create_with_security_attributes_raw(name, &mut attributes as *mut SECURITY_ATTRIBUTES as *mut _)
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn owner_only_descriptor() -> io::Result<usize> {
+    use windows_sys::Win32::Security::Authorization::{
+        ConvertStringSecurityDescriptorToSecurityDescriptorW, SDDL_REVISION_1,
+    };
+
+    let sddl: Vec<u16> = PIPE_SDDL.encode_utf16().chain(Some(0)).collect();
+    let mut descriptor = std::ptr::null_mut();
+    let ok = unsafe {
+        ConvertStringSecurityDescriptorToSecurityDescriptorW(
+            sddl.as_ptr(),
+            SDDL_REVISION_1,
+            &mut descriptor,
+            std::ptr::null_mut(),
+        )
+    };
+    if ok == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(descriptor as usize)
+}
+
+/// Answer commands on one connection until the client hangs up.
+async fn serve<S>(stream: S, app: AppHandle, events: broadcast::Sender<String>)
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (read, mut write) = tokio::io::split(stream);
+    let mut lines = BufReader::new(read).lines();
+    let mut subscription = None;
+
+    loop {
+        let out = tokio::select! {
+            line = lines.next_line() => match line {
+                Ok(Some(line)) if line.trim().is_empty() => continue,
+                Ok(Some(line)) => {
+                    let command = line.trim();
+                    if command == "subscribe" && subscription.is_none() {
+                        subscription = Some(events.subscribe());
+                    }
+                    reply(command, run(&app, command))
+                }
+                // Hung up, or sent something that isn't UTF-8 text
+                _ => break,
+            },
+            Some(event) = next_event(&mut subscription) => event,
+        };
+        if write.write_all(format!("{}\n", out).as_bytes()).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// The next forwarded event, or never if the connection hasn't subscribed.
+async fn next_event(subscription: &mut Option<broadcast::Receiver<String>>) -> Option<String> {
+    let Some(rx) = subscription else {
+        return std::future::pending().await;
+    };
+    loop {
+        match rx.recv().await {
+            Ok(line) => return Some(line),
+            // A slow reader misses events rather than holding up the pipeline
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                log::warn!("Control socket subscriber missed {} events", n);
+            }
+            Err(broadcast::error::RecvError::Closed) => return None,
+        }
+    }
+}
+
+/// One command per line, one JSON reply line per command:
+///
+/// ```text
+/// start | stop | toggle | cancel
+/// set-mode push-to-talk|hands-free     (from the next session on)
+/// get-last-transcript
+/// subscribe
+/// ```
+///
+/// Replies look like `{"type":"reply","command":"start","ok":true,"result":null}`,
+/// with `"ok":false,"error":{code, message, …}` on failure. After `subscribe`
+/// the connection also streams `FORWARDED_EVENTS` as
+/// `{"type":"event","event":"pipeline-state","payload":"recording"}` lines.
+fn run(app: &AppHandle, line: &str) -> Result<Value, PipelineError> {
+    let mut words = line.split_whitespace();
+    match (words.next().unwrap_or_default(), words.next(), words.next()) {
+        ("start", None, None) => commands::begin_dictation(app).map(|_| Value::Null),
+        ("stop", None, None) => commands::end_dictation(app).map(|_| Value::Null),
+        ("toggle", None, None) => commands::toggle_dictation(app).map(|_| Value::Null),
+        ("cancel", None, None) => commands::cancel_dictation(app).map(|_| Value::Null),
+        ("set-mode", Some(mode), None) => set_mode(app, mode),
+        ("get-last-transcript", None, None) => {
+            let state = app.state::<RecordingState>();
+            let cfg = state.config.lock().unwrap();
+            Ok(json!(cfg.history.first()))
+        }
+        ("subscribe", None, None) => Ok(Value::Null),
+        _ => Err(PipelineError::BadRequest(format!("unknown command '{}'", line))),
+    }
+}
+
+/// Switch between push-to-talk and hands-free, as the settings page does.
+fn set_mode(app: &AppHandle, mode: &str) -> Result<Value, PipelineError> {
+    let continuous = match mode {
+        "push-to-talk" => false,
+        "hands-free" => true,
+        other => return Err(PipelineError::BadRequest(format!("unknown mode '{}'", other))),
+    };
+    let state = app.state::<RecordingState>();
+    let mut cfg = state.config.lock().unwrap();
+    cfg.continuous = continuous;
+    config::save_config(&cfg).map_err(|e| PipelineError::Config(e.to_string()))?;
+    drop(cfg);
+    app.emit("config-changed", ()).ok();
+    Ok(json!({ "mode": mode }))
+}
+
+fn reply(command: &str, result: Result<Value, PipelineError>) -> String {
+    match result {
+        Ok(result) => json!({ "type": "reply", "command": command, "ok": true, "result": result }),
+        Err(e) => json!({ "type": "reply", "command": command, "ok": false, "error": e }),
+    }
+    .to_string()
+}
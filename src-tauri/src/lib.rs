@@ -1,12 +1,39 @@
-mod audio;
+#[cfg(feature = "app")]
+mod api;
+pub mod audio;
+#[cfg(feature = "capture")]
+pub mod capture;
+#[cfg(feature = "app")]
 mod commands;
-mod config;
-mod groq;
-mod inject;
+pub mod config;
+#[cfg(feature = "app")]
+mod continuous;
+pub mod decode;
+pub mod dictation;
+pub mod encode;
+pub mod error;
+pub mod groq;
+pub mod hooks;
+pub mod inject;
+#[cfg(feature = "app")]
+mod ipc;
+pub mod meter;
+pub mod pipeline;
+pub mod preprocess;
+#[cfg(feature = "app")]
+mod preroll;
+mod resample;
+pub mod sink;
+pub mod subtitles;
+pub mod transcribe;
+pub mod vad;
 
+#[cfg(feature = "app")]
 use commands::RecordingState;
+#[cfg(feature = "app")]
 use tauri::Manager;
 
+#[cfg(feature = "app")]
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -21,11 +48,23 @@ pub fn run() {
             let cfg = state.config.lock().unwrap().clone();
             
             commands::register_hotkey(&handle, &cfg.hotkey);
+            commands::watch_session_hotkeys(&handle);
+            commands::watch_audio_errors(&handle);
+            commands::watch_input_devices(&handle);
+            commands::watch_preroll(&handle);
+
+            let events = ipc::forward_events(&handle);
+            ipc::start(&handle, events.clone());
+            app.manage(api::ApiServer::new(events));
+            api::apply_config(&handle);
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             commands::start_recording,
             commands::stop_and_process,
+            commands::cancel_recording,
+            commands::pause_recording,
+            commands::resume_recording,
             commands::get_config,
             commands::save_config_cmd,
             commands::get_history,
@@ -33,6 +72,12 @@ pub fn run() {
             commands::delete_history_entry,
             commands::is_recording,
             commands::get_mic_name,
+            commands::get_upload_formats,
+            commands::get_input_devices,
+            commands::test_microphone,
+            commands::transcribe_file,
+            commands::get_pipeline_state,
+            commands::get_preroll_active,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
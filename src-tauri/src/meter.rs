@@ -0,0 +1,295 @@
+//! Input metering. While recording, the capture thread feeds every raw
+//! sample through a [`Meter`] and emits an `audio-meter` reading every 50 ms.
+//! Once capture stops, [`assess`] looks at the whole recording and gives a
+//! verdict, so a bad mic setup is reported as such rather than showing up
+//! only as a poor transcript.
+
+use serde::Serialize;
+
+/// Samples at or above this magnitude count as clipped.
+const CLIP_LEVEL: f32 = 0.98;
+
+/// How fast the noise-floor follower creeps back up after a quiet interval.
+/// Small enough that speech (a few hundred ms at a time) barely moves it.
+const FLOOR_RISE: f32 = 0.02;
+
+/// Frame length used to measure speech level and noise floor.
+const FRAME_MS: u32 = 30;
+
+/// Speech quieter than this (90th-percentile frame RMS) is "too quiet".
+const QUIET_DBFS: f32 = -40.0;
+
+/// More than this share of clipped samples is "clipping".
+const CLIP_RATIO: f32 = 0.001;
+
+/// Less speech-to-background distance than this is "very noisy"…
+const NOISY_SNR_DB: f32 = 10.0;
+
+/// …provided the background itself is audible.
+const NOISY_FLOOR_DBFS: f32 = -50.0;
+
+/// Suggested silence thresholds sit this far above the measured background…
+const THRESHOLD_OVER_FLOOR: f32 = 2.5;
+
+/// …provided speech stands at least this far above it. Any closer and the
+/// test caught no speech to set a threshold against.
+const MIN_THRESHOLD_SNR_DB: f32 = 10.0;
+
+/// …and within these bounds (frame RMS).
+const MIN_THRESHOLD: f32 = 0.001;
+const MAX_THRESHOLD: f32 = 0.05;
+
+/// One `audio-meter` event. Levels are linear (0–1).
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct MeterReading {
+    pub rms: f32,
+    pub peak: f32,
+    /// Samples at or above the clip level since the last reading.
+    pub clipped: usize,
+    /// Running estimate of the background level.
+    pub noise_floor: f32,
+}
+
+/// Accumulates samples between readings.
+#[derive(Default)]
+pub struct Meter {
+    sum_sq: f32,
+    count: usize,
+    peak: f32,
+    clipped: usize,
+    noise_floor: Option<f32>,
+}
+
+impl Meter {
+    pub fn push(&mut self, samples: &[f32]) {
+        for &s in samples {
+            let a = s.abs();
+            self.sum_sq += s * s;
+            self.peak = self.peak.max(a);
+            if a >= CLIP_LEVEL {
+                self.clipped += 1;
+            }
+        }
+        self.count += samples.len();
+    }
+
+    /// Close the current interval. `None` if nothing arrived since the last one.
+    pub fn reading(&mut self) -> Option<MeterReading> {
+        if self.count == 0 {
+            return None;
+        }
+        let rms = (self.sum_sq / self.count as f32).sqrt();
+        // Drops straight to any quieter interval, rises slowly through speech
+        let floor = match self.noise_floor {
+            Some(floor) if rms >= floor => floor + (rms - floor) * FLOOR_RISE,
+            _ => rms,
+        };
+        self.noise_floor = Some(floor);
+
+        let reading = MeterReading { rms, peak: self.peak, clipped: self.clipped, noise_floor: floor };
+        self.sum_sq = 0.0;
+        self.count = 0;
+        self.peak = 0.0;
+        self.clipped = 0;
+        Some(reading)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Ok,
+    TooQuiet,
+    Clipping,
+    VeryNoisy,
+}
+
+/// Whole-recording signal quality, emitted as `recording-quality` and
+/// returned with the transcript.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Quality {
+    pub verdict: Verdict,
+    pub speech_dbfs: f32,
+    pub noise_floor_dbfs: f32,
+    pub peak: f32,
+    pub clipped: usize,
+}
+
+/// Judge a finished recording. `None` for an empty one.
+pub fn assess(samples: &[f32], sample_rate: u32) -> Option<Quality> {
+    let frame_len = (sample_rate * FRAME_MS / 1000).max(1) as usize;
+    let mut levels: Vec<f32> = samples.chunks(frame_len).map(crate::vad::rms).collect();
+    if levels.is_empty() {
+        return None;
+    }
+    levels.sort_by(|a, b| a.total_cmp(b));
+    let speech_dbfs = to_dbfs(levels[levels.len() * 9 / 10]);
+    let noise_floor_dbfs = to_dbfs(levels[levels.len() / 10]);
+
+    let peak = samples.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+    let clipped = samples.iter().filter(|s| s.abs() >= CLIP_LEVEL).count();
+
+    // Clipping first: it distorts every level measured after it
+    let verdict = if clipped as f32 > samples.len() as f32 * CLIP_RATIO {
+        Verdict::Clipping
+    } else if speech_dbfs < QUIET_DBFS {
+        Verdict::TooQuiet
+    } else if noise_floor_dbfs > NOISY_FLOOR_DBFS && speech_dbfs - noise_floor_dbfs < NOISY_SNR_DB {
+        Verdict::VeryNoisy
+    } else {
+        Verdict::Ok
+    };
+
+    Some(Quality { verdict, speech_dbfs, noise_floor_dbfs, peak, clipped })
+}
+
+/// A VAD silence threshold for the mic `quality` was measured on: comfortably
+/// above its background, but never more than half its speech level. `None`
+/// if the recording holds no speech clearly above the background.
+pub fn suggest_threshold(quality: &Quality) -> Option<f32> {
+    if quality.speech_dbfs - quality.noise_floor_dbfs < MIN_THRESHOLD_SNR_DB {
+        return None;
+    }
+    let floor = from_dbfs(quality.noise_floor_dbfs);
+    let speech = from_dbfs(quality.speech_dbfs);
+    let threshold = (floor * THRESHOLD_OVER_FLOOR)
+        .min(speech * 0.5)
+        .clamp(MIN_THRESHOLD, MAX_THRESHOLD);
+    // Only a background louder than MAX_THRESHOLD can push it under
+    (threshold > floor).then_some(threshold)
+}
+
+fn to_dbfs(level: f32) -> f32 {
+    20.0 * level.max(1e-6).log10()
+}
+
+fn from_dbfs(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16_000;
+
+    fn tone(hz: f32, amplitude: f32, secs: f32) -> Vec<f32> {
+        let len = (RATE as f32 * secs) as usize;
+        (0..len)
+            .map(|i| amplitude * (2.0 * std::f32::consts::PI * hz * i as f32 / RATE as f32).sin())
+            .collect()
+    }
+
+    /// Uniform white noise from a fixed seed, so every run hears the same room.
+    fn noise(amplitude: f32, secs: f32) -> Vec<f32> {
+        let mut seed = 0x2545_f491u32;
+        (0..(RATE as f32 * secs) as usize)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                amplitude * ((seed >> 8) as f32 / (1 << 23) as f32 - 1.0)
+            })
+            .collect()
+    }
+
+    fn mix(a: &[f32], b: &[f32]) -> Vec<f32> {
+        a.iter().zip(b).map(|(a, b)| a + b).collect()
+    }
+
+    #[test]
+    fn silence_reads_zero_and_is_too_quiet() {
+        let clip = vec![0.0; RATE as usize];
+        let mut meter = Meter::default();
+        meter.push(&clip);
+        let reading = meter.reading().unwrap();
+        assert_eq!((reading.rms, reading.peak, reading.clipped), (0.0, 0.0, 0));
+        // The interval is closed: nothing new, no reading
+        assert!(meter.reading().is_none());
+
+        assert_eq!(assess(&clip, RATE).unwrap().verdict, Verdict::TooQuiet);
+        assert!(assess(&[], RATE).is_none());
+    }
+
+    #[test]
+    fn clipped_sine_is_clipping() {
+        let clip: Vec<f32> = tone(440.0, 1.5, 1.0).iter().map(|s| s.clamp(-1.0, 1.0)).collect();
+        let mut meter = Meter::default();
+        meter.push(&clip);
+        let reading = meter.reading().unwrap();
+        assert_eq!(reading.peak, 1.0);
+        assert!(reading.clipped > 0);
+
+        let quality = assess(&clip, RATE).unwrap();
+        assert_eq!(quality.verdict, Verdict::Clipping);
+        assert_eq!(quality.clipped, reading.clipped);
+    }
+
+    #[test]
+    fn quiet_sine_is_too_quiet() {
+        let clip = [vec![0.0; 8_000], tone(440.0, 0.005, 1.0), vec![0.0; 8_000]].concat();
+        let quality = assess(&clip, RATE).unwrap();
+        assert_eq!(quality.verdict, Verdict::TooQuiet);
+        assert!(quality.speech_dbfs < QUIET_DBFS);
+    }
+
+    #[test]
+    fn sine_over_quiet_noise_is_ok() {
+        let room = noise(0.01, 2.0);
+        let speech = [vec![0.0; 8_000], tone(440.0, 0.3, 1.0), vec![0.0; 8_000]].concat();
+        let quality = assess(&mix(&room, &speech), RATE).unwrap();
+
+        assert_eq!(quality.verdict, Verdict::Ok);
+        // Uniform noise of amplitude a has an RMS of a/√3
+        assert!((quality.noise_floor_dbfs - to_dbfs(0.01 / 3f32.sqrt())).abs() < 1.0);
+        assert!((quality.speech_dbfs - to_dbfs(0.3 / 2f32.sqrt())).abs() < 1.0);
+    }
+
+    #[test]
+    fn sine_over_loud_noise_is_very_noisy() {
+        let clip = mix(&noise(0.1, 2.0), &tone(440.0, 0.1, 2.0));
+        let quality = assess(&clip, RATE).unwrap();
+        assert_eq!(quality.verdict, Verdict::VeryNoisy);
+        assert!(quality.speech_dbfs - quality.noise_floor_dbfs < NOISY_SNR_DB);
+    }
+
+    #[test]
+    fn suggested_threshold_sits_between_floor_and_speech() {
+        for room in [0.0, 0.002, 0.01, 0.03] {
+            let speech = [vec![0.0; 8_000], tone(440.0, 0.3, 1.0), vec![0.0; 8_000]].concat();
+            let quality = assess(&mix(&noise(room, 2.0), &speech), RATE).unwrap();
+            let threshold = suggest_threshold(&quality).unwrap();
+
+            let floor = from_dbfs(quality.noise_floor_dbfs);
+            assert!(threshold > floor, "{} under the floor {} (noise {})", threshold, floor, room);
+            assert!(threshold < from_dbfs(quality.speech_dbfs), "{} over the speech (noise {})", threshold, room);
+        }
+    }
+
+    #[test]
+    fn no_threshold_without_speech() {
+        for clip in [noise(0.01, 2.0), tone(440.0, 0.3, 2.0), mix(&noise(0.1, 2.0), &tone(440.0, 0.1, 2.0))] {
+            let quality = assess(&clip, RATE).unwrap();
+            assert_eq!(suggest_threshold(&quality), None, "{:?}", quality);
+        }
+    }
+
+    #[test]
+    fn noise_floor_ignores_speech_and_drops_back() {
+        let room = noise(0.01, 0.05);
+        let speech = tone(440.0, 0.3, 0.05);
+        let mut meter = Meter::default();
+
+        let mut quiet = 0.0;
+        for _ in 0..10 {
+            meter.push(&room);
+            quiet = meter.reading().unwrap().noise_floor;
+        }
+        for _ in 0..10 {
+            meter.push(&speech);
+            let reading = meter.reading().unwrap();
+            assert!(reading.noise_floor < reading.rms / 4.0, "floor {} rose with speech", reading.noise_floor);
+        }
+        meter.push(&room);
+        let reading = meter.reading().unwrap();
+        assert!((reading.noise_floor - quiet).abs() < 0.001);
+    }
+}
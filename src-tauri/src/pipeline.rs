@@ -0,0 +1,214 @@
+#[cfg(feature = "app")]
+use crate::error::PipelineError;
+use serde::Serialize;
+#[cfg(feature = "app")]
+use std::sync::Mutex;
+#[cfg(feature = "app")]
+use tauri::{AppHandle, Emitter};
+
+/// Where a dictation session is. Every change is broadcast as a
+/// `pipeline-state` event.
+///
+/// ```text
+/// Idle → Recording → Transcribing → Refining → Injecting → Idle
+///          ↕ Paused ──────↗ └──────────────────────────────→ Idle (continuous)
+///            └──────────┴──────────────┴──→ Cancelled → Idle
+///            └──────────┴──────────────┴──────────┴──→ Error → Idle
+/// Idle → Testing → Idle
+/// ```
+///
+/// Paused can end the same ways Recording can. Testing holds the microphone
+/// for `test_microphone`, so no dictation can start meanwhile.
+///
+/// In continuous mode segments are injected while still Recording, so the
+/// session ends straight from Transcribing once the last one is in. Only
+/// `Pipeline::finish_hands_free` takes that shortcut.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineState {
+    #[default]
+    Idle,
+    Recording,
+    Paused,
+    Transcribing,
+    Refining,
+    Injecting,
+    Cancelled,
+    Error,
+    Testing,
+}
+
+impl PipelineState {
+    pub fn can_transition_to(self, next: PipelineState) -> bool {
+        use PipelineState::*;
+        matches!(
+            (self, next),
+            (Idle, Recording)
+                | (Recording, Paused)
+                | (Paused, Recording)
+                | (Recording | Paused, Transcribing)
+                | (Transcribing, Refining)
+                | (Refining, Injecting)
+                | (Injecting, Idle)
+                | (Recording | Paused | Transcribing | Refining, Cancelled)
+                | (Recording | Paused | Transcribing | Refining | Injecting, Error)
+                | (Cancelled | Error, Idle)
+                | (Idle, Testing)
+                | (Testing, Idle)
+        )
+    }
+
+    /// True while a session is in progress (anything but Idle).
+    pub fn is_busy(self) -> bool {
+        self != PipelineState::Idle
+    }
+
+    /// True while the session still owns the microphone, paused or not.
+    pub fn is_capturing(self) -> bool {
+        matches!(self, PipelineState::Recording | PipelineState::Paused)
+    }
+}
+
+/// Guards the session state so check-and-set is atomic: two hotkey presses
+/// racing each other can never both start a recording.
+#[cfg(feature = "app")]
+#[derive(Default)]
+pub struct Pipeline {
+    state: Mutex<PipelineState>,
+}
+
+#[cfg(feature = "app")]
+impl Pipeline {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(PipelineState::Idle),
+        }
+    }
+
+    pub fn get(&self) -> PipelineState {
+        *self.state.lock().unwrap()
+    }
+
+    /// Move to `next`, or reject the transition if the machine doesn't allow it.
+    /// Returns the state we left.
+    pub fn transition(&self, next: PipelineState, app: &AppHandle) -> Result<PipelineState, PipelineError> {
+        self.transition_if(next, app, |prev| prev.can_transition_to(next))
+    }
+
+    /// Like `transition`, but only out of `from`. Resuming must not start a
+    /// recording from Idle even though Idle → Recording is a legal move.
+    pub fn transition_from(
+        &self,
+        from: PipelineState,
+        next: PipelineState,
+        app: &AppHandle,
+    ) -> Result<(), PipelineError> {
+        self.transition_if(next, app, |prev| prev == from && prev.can_transition_to(next))
+            .map(|_| ())
+    }
+
+    /// End a hands-free session once its last segment is in. Nothing is
+    /// left to inject, so it goes from Transcribing straight to Idle, which
+    /// a push-to-talk dictation may not do.
+    pub fn finish_hands_free(&self, app: &AppHandle) -> Result<(), PipelineError> {
+        self.transition_if(PipelineState::Idle, app, |prev| prev == PipelineState::Transcribing)
+            .map(|_| ())
+    }
+
+    fn transition_if(
+        &self,
+        next: PipelineState,
+        app: &AppHandle,
+        allowed: impl Fn(PipelineState) -> bool,
+    ) -> Result<PipelineState, PipelineError> {
+        let prev = {
+            let mut state = self.state.lock().unwrap();
+            let prev = *state;
+            if !allowed(prev) {
+                return Err(match (prev, next) {
+                    (PipelineState::Recording, PipelineState::Recording) => PipelineError::AlreadyRecording,
+                    (_, PipelineState::Transcribing | PipelineState::Paused) => PipelineError::NotRecording,
+                    _ => PipelineError::InvalidTransition {
+                        from: format!("{:?}", prev),
+                        to: format!("{:?}", next),
+                    },
+                });
+            }
+            *state = next;
+            prev
+        };
+
+        log::info!("Pipeline state: {:?} → {:?}", prev, next);
+        app.emit("pipeline-state", next).ok();
+        Ok(prev)
+    }
+
+    /// End the session in a terminal state (`Cancelled` or `Error`) and return to Idle.
+    pub fn finish(&self, terminal: PipelineState, app: &AppHandle) {
+        if self.transition(terminal, app).is_err() {
+            log::warn!("Pipeline: cannot enter {:?} from {:?}", terminal, self.get());
+        }
+        self.transition(PipelineState::Idle, app).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PipelineState::{self, *};
+
+    const ALL: [PipelineState; 9] = [Idle, Recording, Paused, Transcribing, Refining, Injecting, Cancelled, Error, Testing];
+
+    /// Every legal move; anything else must be rejected.
+    const ALLOWED: &[(PipelineState, PipelineState)] = &[
+        (Idle, Recording),
+        (Recording, Paused),
+        (Recording, Transcribing),
+        (Recording, Cancelled),
+        (Recording, Error),
+        (Paused, Recording),
+        (Paused, Transcribing),
+        (Paused, Cancelled),
+        (Paused, Error),
+        (Transcribing, Refining),
+        (Transcribing, Cancelled),
+        (Transcribing, Error),
+        (Refining, Injecting),
+        (Refining, Cancelled),
+        (Refining, Error),
+        (Injecting, Idle),
+        (Injecting, Error),
+        (Cancelled, Idle),
+        (Error, Idle),
+        (Idle, Testing),
+        (Testing, Idle),
+    ];
+
+    #[test]
+    fn transition_table() {
+        for from in ALL {
+            for to in ALL {
+                assert_eq!(
+                    from.can_transition_to(to),
+                    ALLOWED.contains(&(from, to)),
+                    "{:?} → {:?}",
+                    from,
+                    to
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn push_to_talk_cannot_skip_injection() {
+        assert!(!Transcribing.can_transition_to(Idle));
+        assert!(!Refining.can_transition_to(Idle));
+    }
+
+    #[test]
+    fn busy_and_capturing() {
+        for state in ALL {
+            assert_eq!(state.is_busy(), state != Idle, "{:?}", state);
+            assert_eq!(state.is_capturing(), matches!(state, Recording | Paused), "{:?}", state);
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! Optional clean-up between capture and upload, for quiet speakers and
+//! noisy rooms. Each stage is toggled in `AppConfig.preprocess`:
+//!
+//! 1. High-pass filter — removes rumble, desk thumps and mains hum below
+//!    the voice band.
+//! 2. Noise suppression — RNNoise (via `nnnoiseless`), which runs at 48 kHz,
+//!    so the clip is resampled up and back down around it.
+//! 3. Gain control — one clip-wide gain that brings the speech level to a
+//!    target, capped so a near-silent clip isn't blown up into hiss, and
+//!    limited so peaks never clip.
+
+use crate::config::PreprocessConfig;
+use crate::resample;
+use nnnoiseless::DenoiseState;
+
+/// RNNoise's native rate.
+const DENOISE_RATE: u32 = 48_000;
+
+/// Frame length used to measure speech level for gain control.
+const LEVEL_FRAME_MS: u32 = 30;
+
+/// Highest allowed peak after gain control (about -0.1 dBFS).
+const PEAK_LIMIT: f32 = 0.99;
+
+/// Run the enabled stages over `samples`. Returns the input untouched when
+/// everything is off.
+pub fn process(samples: &[f32], sample_rate: u32, cfg: &PreprocessConfig) -> Vec<f32> {
+    let mut out = samples.to_vec();
+    if cfg.high_pass {
+        high_pass(&mut out, sample_rate, cfg.high_pass_hz);
+    }
+    if cfg.noise_suppression {
+        out = denoise(&out, sample_rate);
+    }
+    if cfg.agc {
+        normalize(&mut out, sample_rate, cfg.agc_target_dbfs, cfg.agc_max_gain_db);
+    }
+    out
+}
+
+/// Second-order Butterworth high-pass (RBJ biquad), applied in place.
+pub fn high_pass(samples: &mut [f32], sample_rate: u32, cutoff_hz: f32) {
+    let w0 = 2.0 * std::f32::consts::PI * cutoff_hz / sample_rate as f32;
+    let alpha = w0.sin() / std::f32::consts::SQRT_2; // Q = 1/√2
+    let cos = w0.cos();
+    let a0 = 1.0 + alpha;
+    let b0 = (1.0 + cos) / 2.0 / a0;
+    let b1 = -(1.0 + cos) / a0;
+    let b2 = b0;
+    let a1 = -2.0 * cos / a0;
+    let a2 = (1.0 - alpha) / a0;
+
+    let (mut x1, mut x2, mut y1, mut y2) = (0.0f32, 0.0f32, 0.0f32, 0.0f32);
+    for s in samples.iter_mut() {
+        let x = *s;
+        let y = b0 * x + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2;
+        x2 = x1;
+        x1 = x;
+        y2 = y1;
+        y1 = y;
+        *s = y;
+    }
+}
+
+/// RNNoise over the whole clip. Output has the same length as the input.
+pub fn denoise(samples: &[f32], sample_rate: u32) -> Vec<f32> {
+    const FRAME: usize = DenoiseState::FRAME_SIZE;
+
+    // RNNoise expects 16-bit scale at 48 kHz
+    let mut input: Vec<f32> = resample::resample(samples, sample_rate, DENOISE_RATE)
+        .into_iter()
+        .map(|s| s * i16::MAX as f32)
+        .collect();
+    let len = input.len();
+    // Output lags input by one frame: feed one extra frame of silence and
+    // drop the first (fade-in) frame to line the result back up
+    input.resize(((len + FRAME - 1) / FRAME + 1) * FRAME, 0.0);
+
+    let mut state = DenoiseState::new();
+    let mut out = Vec::with_capacity(input.len());
+    let mut frame = [0.0f32; FRAME];
+    for (i, chunk) in input.chunks_exact(FRAME).enumerate() {
+        state.process_frame(&mut frame, chunk);
+        if i > 0 {
+            out.extend(frame.iter().map(|s| s / i16::MAX as f32));
+        }
+    }
+    out.truncate(len);
+
+    let mut cleaned = resample::resample(&out, DENOISE_RATE, sample_rate);
+    cleaned.resize(samples.len(), 0.0);
+    cleaned
+}
+
+/// Scale the clip so its speech level (90th-percentile frame RMS) reaches
+/// `target_dbfs`, with at most `max_gain_db` of boost and no clipping.
+pub fn normalize(samples: &mut [f32], sample_rate: u32, target_dbfs: f32, max_gain_db: f32) {
+    let frame_len = (sample_rate * LEVEL_FRAME_MS / 1000).max(1) as usize;
+    let mut levels: Vec<f32> = samples.chunks(frame_len).map(crate::vad::rms).collect();
+    if levels.is_empty() {
+        return;
+    }
+    levels.sort_by(|a, b| a.total_cmp(b));
+    let speech_level = levels[levels.len() * 9 / 10];
+    if speech_level <= f32::EPSILON {
+        return;
+    }
+
+    let target = db_to_gain(target_dbfs);
+    let peak = samples.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+    let gain = (target / speech_level)
+        .min(db_to_gain(max_gain_db))
+        .min(PEAK_LIMIT / peak.max(f32::EPSILON));
+
+    log::info!(
+        "AGC: speech level {:.1} dBFS, gain {:+.1} dB",
+        20.0 * speech_level.log10(),
+        20.0 * gain.log10()
+    );
+    samples.iter_mut().for_each(|s| *s *= gain);
+}
+
+fn db_to_gain(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
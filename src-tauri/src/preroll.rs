@@ -0,0 +1,63 @@
+//! Optional pre-roll: keep the mic open while idle so a recording can start
+//! with the last few hundred milliseconds, held in memory only.
+
+use crate::audio;
+use crate::capture::{self, CaptureEvent, CaptureInfo, RollingCapture};
+use anyhow::Result;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter};
+
+/// Longest pre-roll accepted from the config.
+const MAX_PREROLL_MS: u32 = 2000;
+
+/// The buffer is overwritten continuously and leaves memory only through
+/// [`PreRoll::into_recording`]. Every start/stop emits `preroll-active` so
+/// the UI can show that the microphone is open.
+pub struct PreRoll {
+    ms: u32,
+    capture: RollingCapture,
+}
+
+impl PreRoll {
+    /// Open the default microphone and start keeping the last `ms` of audio.
+    pub async fn start(ms: u32, app: &AppHandle) -> Result<Self> {
+        let capacity = (ms.min(MAX_PREROLL_MS) * audio::TARGET_SAMPLE_RATE / 1000) as usize;
+        let capture = capture::start_rolling_capture(capacity).await?;
+        log::info!("Pre-roll listening on {} ({} ms, memory only)", capture.info.device, ms);
+        app.emit("preroll-active", true).ok();
+        Ok(Self { ms, capture })
+    }
+
+    /// False once the stream has died (e.g. the mic was unplugged).
+    pub fn is_running(&self) -> bool {
+        self.capture.is_running()
+    }
+
+    pub fn ms(&self) -> u32 {
+        self.ms
+    }
+
+    /// Hand the open stream over to a recording, which starts with what was
+    /// buffered; see [`RollingCapture::into_recording`]. `None` if the
+    /// stream had died, and the caller has to open the mic itself.
+    pub fn into_recording(
+        self,
+        app: &AppHandle,
+        samples: Arc<Mutex<Vec<f32>>>,
+        is_recording: Arc<AtomicBool>,
+        paused: Arc<AtomicBool>,
+        notify: impl Fn(CaptureEvent) + Send + 'static,
+    ) -> Option<CaptureInfo> {
+        let info = self.capture.into_recording(samples, is_recording, paused, notify);
+        app.emit("preroll-active", false).ok();
+        info
+    }
+
+    /// Close the microphone and discard the buffer.
+    pub fn stop(self, app: &AppHandle) {
+        self.capture.stop();
+        app.emit("preroll-active", false).ok();
+        log::info!("Pre-roll stopped");
+    }
+}
@@ -0,0 +1,311 @@
+//! Band-limited sample-rate conversion.
+//!
+//! A rational polyphase FIR resampler (upsample by L, low-pass, downsample
+//! by M) with a Kaiser-windowed sinc filter. The filter cuts off just below
+//! the lower of the two Nyquist frequencies, so 48 kHz → 16 kHz no longer
+//! folds 8–24 kHz energy (sibilants, fan noise) back into the speech band.
+//!
+//! It is streaming: feed chunks as they arrive with [`Resampler::process`]
+//! and call [`Resampler::flush`] once at the end.
+
+/// Stopband attenuation of the anti-aliasing filter, in dB.
+const STOPBAND_DB: f64 = 80.0;
+
+/// Passband edge as a fraction of the output (or input) Nyquist frequency.
+const PASSBAND: f64 = 0.9;
+
+pub struct Resampler {
+    /// Upsampling factor L.
+    up: usize,
+    /// Downsampling factor M.
+    down: usize,
+    /// Taps per polyphase branch (filter length in input samples).
+    taps: usize,
+    /// Phase-major coefficients: `phases[p * taps + k]`, gain L folded in.
+    phases: Vec<f32>,
+    /// Group delay of the filter in upsampled samples.
+    delay: u64,
+    /// Recent input; `history[0]` is absolute input index `history_start`.
+    history: Vec<f32>,
+    history_start: u64,
+    /// Total input samples seen.
+    consumed: u64,
+    /// Index of the next output sample.
+    produced: u64,
+}
+
+impl Resampler {
+    pub fn new(from_rate: u32, to_rate: u32) -> Self {
+        let g = gcd(from_rate as usize, to_rate as usize).max(1);
+        let up = to_rate as usize / g;
+        let down = from_rate as usize / g;
+
+        if up == down {
+            // Same rate: a single unit tap, i.e. pass-through
+            return Self {
+                up,
+                down,
+                taps: 1,
+                phases: vec![1.0],
+                delay: 0,
+                history: Vec::new(),
+                history_start: 0,
+                consumed: 0,
+                produced: 0,
+            };
+        }
+
+        // Transition band from PASSBAND·nyq to nyq, in cycles per input sample
+        let nyquist = from_rate.min(to_rate) as f64 / 2.0;
+        let transition = nyquist * (1.0 - PASSBAND) / from_rate as f64;
+        let taps = kaiser_length(transition).max(2);
+        let beta = kaiser_beta(STOPBAND_DB);
+
+        // Prototype low-pass at the upsampled rate, cut off halfway through the transition band
+        let len = taps * up;
+        let center = (len - 1) as f64 / 2.0;
+        let cutoff = nyquist * (1.0 + PASSBAND) / 2.0 / (from_rate as f64 * up as f64);
+        let i0_beta = bessel_i0(beta);
+        let prototype: Vec<f64> = (0..len)
+            .map(|j| {
+                let x = j as f64 - center;
+                let sinc = if x == 0.0 {
+                    2.0 * cutoff
+                } else {
+                    (2.0 * std::f64::consts::PI * cutoff * x).sin() / (std::f64::consts::PI * x)
+                };
+                let r = 2.0 * j as f64 / (len - 1) as f64 - 1.0;
+                let window = bessel_i0(beta * (1.0 - r * r).max(0.0).sqrt()) / i0_beta;
+                sinc * window * up as f64
+            })
+            .collect();
+
+        let mut phases = vec![0.0f32; len];
+        for p in 0..up {
+            for k in 0..taps {
+                phases[p * taps + k] = prototype[p + k * up] as f32;
+            }
+        }
+
+        Self {
+            up,
+            down,
+            taps,
+            phases,
+            delay: (len / 2) as u64,
+            history: Vec::new(),
+            history_start: 0,
+            consumed: 0,
+            produced: 0,
+        }
+    }
+
+    /// Resample the next chunk of input, appending output to `out`.
+    pub fn process(&mut self, input: &[f32], out: &mut Vec<f32>) {
+        self.history.extend_from_slice(input);
+        self.consumed += input.len() as u64;
+        self.emit(self.consumed, out);
+        self.trim_history();
+    }
+
+    /// Emit the filter tail. After this, the total output length is
+    /// `input_len · to_rate / from_rate` (rounded down).
+    pub fn flush(&mut self, out: &mut Vec<f32>) {
+        // Input past the end reads as silence in `sample_at`
+        let target = self.consumed * self.up as u64 / self.down as u64;
+        while self.produced < target {
+            let t = self.produced * self.down as u64 + self.delay;
+            out.push(self.sample_at(t));
+            self.produced += 1;
+        }
+    }
+
+    /// Produce every output whose newest input sample index is below `limit`.
+    fn emit(&mut self, limit: u64, out: &mut Vec<f32>) {
+        loop {
+            let t = self.produced * self.down as u64 + self.delay;
+            if t / self.up as u64 >= limit {
+                break;
+            }
+            out.push(self.sample_at(t));
+            self.produced += 1;
+        }
+    }
+
+    /// Evaluate the filter at upsampled time `t`.
+    fn sample_at(&self, t: u64) -> f32 {
+        let base = t / self.up as u64;
+        let phase = (t % self.up as u64) as usize;
+        let coeffs = &self.phases[phase * self.taps..(phase + 1) * self.taps];
+        let mut acc = 0.0f32;
+        for (k, &c) in coeffs.iter().enumerate() {
+            let Some(idx) = base.checked_sub(k as u64) else {
+                break;
+            };
+            if let Some(&x) = idx
+                .checked_sub(self.history_start)
+                .and_then(|i| self.history.get(i as usize))
+            {
+                acc += c * x;
+            }
+        }
+        acc
+    }
+
+    /// Drop input that no future output can reach.
+    fn trim_history(&mut self) {
+        let next_base = (self.produced * self.down as u64 + self.delay) / self.up as u64;
+        let keep_from = next_base.saturating_sub(self.taps as u64);
+        if keep_from > self.history_start {
+            let drop = ((keep_from - self.history_start) as usize).min(self.history.len());
+            self.history.drain(..drop);
+            self.history_start += drop as u64;
+        }
+    }
+}
+
+/// One-shot conversion of a whole buffer.
+pub fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == to_rate {
+        return samples.to_vec();
+    }
+    let mut resampler = Resampler::new(from_rate, to_rate);
+    let mut out = Vec::with_capacity(samples.len() * to_rate as usize / from_rate as usize + 1);
+    resampler.process(samples, &mut out);
+    resampler.flush(&mut out);
+    out
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// Kaiser's estimate of the taps needed for `STOPBAND_DB` over a transition
+/// band of `width` cycles/sample.
+fn kaiser_length(width: f64) -> usize {
+    ((STOPBAND_DB - 8.0) / (2.285 * 2.0 * std::f64::consts::PI * width)).ceil() as usize + 1
+}
+
+fn kaiser_beta(attenuation: f64) -> f64 {
+    if attenuation > 50.0 {
+        0.1102 * (attenuation - 8.7)
+    } else if attenuation >= 21.0 {
+        0.5842 * (attenuation - 21.0).powf(0.4) + 0.07886 * (attenuation - 21.0)
+    } else {
+        0.0
+    }
+}
+
+/// Zeroth-order modified Bessel function of the first kind (power series).
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let half = x / 2.0;
+    for k in 1..50 {
+        term *= (half / k as f64) * (half / k as f64);
+        sum += term;
+        if term < sum * 1e-12 {
+            break;
+        }
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TARGET: u32 = 16_000;
+
+    fn tone(hz: f32, rate: u32, secs: f32) -> Vec<f32> {
+        let len = (rate as f32 * secs) as usize;
+        (0..len)
+            .map(|i| (2.0 * std::f32::consts::PI * hz * i as f32 / rate as f32).sin())
+            .collect()
+    }
+
+    /// RMS of the middle half, away from the filter's start-up and tail.
+    fn steady_rms(samples: &[f32]) -> f32 {
+        let middle = &samples[samples.len() / 4..samples.len() * 3 / 4];
+        (middle.iter().map(|s| s * s).sum::<f32>() / middle.len() as f32).sqrt()
+    }
+
+    fn db(ratio: f32) -> f32 {
+        20.0 * ratio.log10()
+    }
+
+    #[test]
+    fn output_length_follows_the_rate_ratio() {
+        for (rate, expected) in [(44_100, 16_000), (48_000, 16_000), (16_000, 16_000)] {
+            let out = resample(&vec![0.0; rate as usize], rate, TARGET);
+            assert_eq!(out.len(), expected, "{} Hz", rate);
+        }
+        // Rounded down when the ratio doesn't divide the length
+        assert_eq!(resample(&[0.0; 1000], 44_100, TARGET).len(), 362);
+    }
+
+    #[test]
+    fn passband_tone_keeps_its_level() {
+        for rate in [44_100, 48_000] {
+            let input = tone(1_000.0, rate, 0.5);
+            let out = resample(&input, rate, TARGET);
+            let change = db(steady_rms(&out) / steady_rms(&input));
+            assert!(
+                change.abs() < 0.1,
+                "{} Hz: 1 kHz changed {:.2} dB",
+                rate,
+                change
+            );
+        }
+    }
+
+    #[test]
+    fn tone_above_new_nyquist_is_attenuated() {
+        // 10 kHz would fold back to 6 kHz without the anti-aliasing filter
+        for rate in [44_100, 48_000] {
+            let input = tone(10_000.0, rate, 0.5);
+            let out = resample(&input, rate, TARGET);
+            let level = db(steady_rms(&out) / steady_rms(&input));
+            assert!(
+                level < -60.0,
+                "{} Hz: 10 kHz only down {:.1} dB",
+                rate,
+                level
+            );
+        }
+    }
+
+    #[test]
+    fn empty_input_gives_empty_output() {
+        assert!(resample(&[], 48_000, TARGET).is_empty());
+        let mut out = Vec::new();
+        let mut resampler = Resampler::new(48_000, TARGET);
+        resampler.process(&[], &mut out);
+        resampler.flush(&mut out);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn single_sample_input() {
+        // Too short to yield a sample when downsampling, two when doubling
+        assert!(resample(&[0.5], 48_000, TARGET).is_empty());
+        let up = resample(&[0.5], 8_000, TARGET);
+        assert_eq!(up.len(), 2);
+        assert!(up.iter().all(|s| s.is_finite()));
+    }
+
+    #[test]
+    fn equal_rates_pass_through() {
+        let input = tone(440.0, TARGET, 0.1);
+        assert_eq!(resample(&input, TARGET, TARGET), input);
+
+        // The streaming path too
+        let mut out = Vec::new();
+        let mut resampler = Resampler::new(TARGET, TARGET);
+        for chunk in input.chunks(100) {
+            resampler.process(chunk, &mut out);
+        }
+        resampler.flush(&mut out);
+        assert_eq!(out, input);
+    }
+}
@@ -0,0 +1,108 @@
+//! Where the capture thread puts resampled audio, and the switch that turns
+//! a running pre-roll into a recording.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+pub enum Sink {
+    /// Keep everything: a recording.
+    Append(Arc<Mutex<Vec<f32>>>),
+    /// Keep only the newest `capacity` samples: the pre-roll.
+    Rolling(VecDeque<f32>, usize),
+}
+
+impl Sink {
+    pub fn push(&mut self, chunk: &[f32]) {
+        match self {
+            Sink::Append(samples) => samples.lock().unwrap().extend_from_slice(chunk),
+            Sink::Rolling(buffer, capacity) => {
+                buffer.extend(chunk.iter().copied());
+                let excess = buffer.len().saturating_sub(*capacity);
+                buffer.drain(..excess);
+            }
+        }
+    }
+
+    /// Append from now on to `samples`, starting with whatever a rolling
+    /// sink was holding. As the same sink keeps receiving the stream, the
+    /// seam has no gap and nothing is repeated.
+    pub fn switch_to(&mut self, samples: Arc<Mutex<Vec<f32>>>) {
+        if let Sink::Rolling(buffer, _) = self {
+            samples.lock().unwrap().splice(0..0, buffer.drain(..));
+        }
+        *self = Sink::Append(samples);
+    }
+
+    /// Forget anything buffered.
+    pub fn clear(&mut self) {
+        if let Sink::Rolling(buffer, _) = self {
+            buffer.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Consecutive "samples", so a gap or repeat at the seam shows up.
+    fn ramp(range: std::ops::Range<u32>) -> Vec<f32> {
+        range.map(|i| i as f32).collect()
+    }
+
+    #[test]
+    fn rolling_keeps_the_newest_samples() {
+        let mut sink = Sink::Rolling(VecDeque::new(), 5);
+        sink.push(&ramp(0..4));
+        sink.push(&ramp(4..9));
+        let Sink::Rolling(buffer, _) = &sink else { unreachable!() };
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), ramp(4..9));
+    }
+
+    #[test]
+    fn switch_splices_pre_roll_and_stream_without_seam() {
+        let samples = Arc::new(Mutex::new(Vec::new()));
+        let mut sink = Sink::Rolling(VecDeque::new(), 8);
+        sink.push(&ramp(0..20));
+
+        sink.switch_to(Arc::clone(&samples));
+        sink.push(&ramp(20..30));
+        sink.push(&ramp(30..35));
+
+        assert_eq!(*samples.lock().unwrap(), ramp(12..35));
+    }
+
+    #[test]
+    fn switch_mid_stream_loses_and_repeats_nothing() {
+        // The capture thread keeps pushing while the recording takes over
+        let sink = Arc::new(Mutex::new(Sink::Rolling(VecDeque::new(), 1600)));
+        let feeder = {
+            let sink = Arc::clone(&sink);
+            std::thread::spawn(move || {
+                for start in (0..20_000).step_by(160) {
+                    sink.lock().unwrap().push(&ramp(start..start + 160));
+                    std::thread::yield_now();
+                }
+            })
+        };
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        let samples = Arc::new(Mutex::new(Vec::new()));
+        sink.lock().unwrap().switch_to(Arc::clone(&samples));
+        feeder.join().unwrap();
+
+        let samples = samples.lock().unwrap();
+        assert!(!samples.is_empty());
+        assert_eq!(*samples.last().unwrap(), 19_999.0);
+        assert!(samples.windows(2).all(|w| w[1] == w[0] + 1.0), "seam has a gap or a repeat");
+    }
+
+    #[test]
+    fn clear_forgets_the_pre_roll() {
+        let mut sink = Sink::Rolling(VecDeque::new(), 8);
+        sink.push(&ramp(0..8));
+        sink.clear();
+        let samples = Arc::new(Mutex::new(Vec::new()));
+        sink.switch_to(Arc::clone(&samples));
+        assert!(samples.lock().unwrap().is_empty());
+    }
+}
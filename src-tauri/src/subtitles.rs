@@ -0,0 +1,147 @@
+//! SRT and WebVTT output for file transcriptions, built from Whisper's
+//! segment timestamps.
+
+use crate::groq::TranscriptSegment;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubtitleFormat {
+    Srt,
+    Vtt,
+}
+
+impl SubtitleFormat {
+    /// Parse the format name passed to `transcribe_file`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "srt" => Some(Self::Srt),
+            "vtt" => Some(Self::Vtt),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Srt => "srt",
+            Self::Vtt => "vtt",
+        }
+    }
+}
+
+/// Render `segments` as one subtitle file. Segments without text are skipped.
+pub fn write(segments: &[TranscriptSegment], format: SubtitleFormat) -> String {
+    let mut out = String::new();
+    if format == SubtitleFormat::Vtt {
+        out.push_str("WEBVTT\n\n");
+    }
+
+    let cues = segments
+        .iter()
+        .map(|s| (s, cue_text(&s.text)))
+        .filter(|(_, text)| !text.is_empty());
+    for (i, (seg, text)) in cues.enumerate() {
+        if format == SubtitleFormat::Srt {
+            writeln!(out, "{}", i + 1).ok();
+        }
+        // A cue must end after it starts, even if Whisper says otherwise
+        let end = seg.end.max(seg.start + 0.001);
+        writeln!(out, "{} --> {}", timestamp(seg.start, format), timestamp(end, format)).ok();
+        writeln!(out, "{}\n", text).ok();
+    }
+    out
+}
+
+/// Segment text as cue lines. A blank line ends a cue in both formats, so
+/// any inside the text (e.g. from refinement) are dropped.
+fn cue_text(text: &str) -> String {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// `HH:MM:SS,mmm` for SRT, `HH:MM:SS.mmm` for WebVTT.
+fn timestamp(secs: f64, format: SubtitleFormat) -> String {
+    let ms = (secs.max(0.0) * 1000.0).round() as u64;
+    let sep = match format {
+        SubtitleFormat::Srt => ',',
+        SubtitleFormat::Vtt => '.',
+    };
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        sep,
+        ms % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seg(start: f64, end: f64, text: &str) -> TranscriptSegment {
+        TranscriptSegment { start, end, text: text.into() }
+    }
+
+    #[test]
+    fn srt_numbers_cues_with_comma_timestamps() {
+        let segments = [seg(0.0, 1.5, " مرحبا "), seg(1.5, 3.25, "كيف الحال")];
+        assert_eq!(
+            write(&segments, SubtitleFormat::Srt),
+            "1\n00:00:00,000 --> 00:00:01,500\nمرحبا\n\n2\n00:00:01,500 --> 00:00:03,250\nكيف الحال\n\n"
+        );
+    }
+
+    #[test]
+    fn vtt_has_header_and_dot_timestamps() {
+        let segments = [seg(0.0, 1.5, "مرحبا"), seg(1.5, 3.25, "كيف الحال")];
+        assert_eq!(
+            write(&segments, SubtitleFormat::Vtt),
+            "WEBVTT\n\n00:00:00.000 --> 00:00:01.500\nمرحبا\n\n00:00:01.500 --> 00:00:03.250\nكيف الحال\n\n"
+        );
+    }
+
+    #[test]
+    fn timestamps_round_to_the_millisecond_and_carry() {
+        assert_eq!(timestamp(0.0004, SubtitleFormat::Srt), "00:00:00,000");
+        assert_eq!(timestamp(0.0006, SubtitleFormat::Srt), "00:00:00,001");
+        assert_eq!(timestamp(0.9996, SubtitleFormat::Srt), "00:00:01,000");
+        assert_eq!(timestamp(59.9996, SubtitleFormat::Vtt), "00:01:00.000");
+        assert_eq!(timestamp(3599.9996, SubtitleFormat::Vtt), "01:00:00.000");
+        assert_eq!(timestamp(-1.0, SubtitleFormat::Srt), "00:00:00,000");
+    }
+
+    #[test]
+    fn minutes_roll_over_into_hours() {
+        // 100 minutes, then 100 hours
+        assert_eq!(timestamp(6000.0, SubtitleFormat::Srt), "01:40:00,000");
+        assert_eq!(timestamp(360_000.25, SubtitleFormat::Vtt), "100:00:00.250");
+    }
+
+    #[test]
+    fn empty_segments_are_skipped_without_gaps_in_numbering() {
+        let segments = [seg(0.0, 1.0, "one"), seg(1.0, 2.0, "  \n "), seg(2.0, 3.0, "two")];
+        let srt = write(&segments, SubtitleFormat::Srt);
+        assert!(srt.starts_with("1\n00:00:00,000"));
+        assert!(srt.contains("\n\n2\n00:00:02,000 --> 00:00:03,000\ntwo\n\n"), "{}", srt);
+        assert!(!srt.contains("\n3\n"));
+    }
+
+    #[test]
+    fn blank_lines_inside_text_do_not_end_the_cue() {
+        let segments = [seg(0.0, 1.0, "first line\n\nsecond line\r\n\r\nthird"), seg(1.0, 2.0, "next")];
+        assert_eq!(
+            write(&segments, SubtitleFormat::Srt),
+            "1\n00:00:00,000 --> 00:00:01,000\nfirst line\nsecond line\nthird\n\n2\n00:00:01,000 --> 00:00:02,000\nnext\n\n"
+        );
+    }
+
+    #[test]
+    fn zero_length_cue_still_ends_after_it_starts() {
+        let srt = write(&[seg(5.0, 4.0, "late")], SubtitleFormat::Srt);
+        assert!(srt.contains("00:00:05,000 --> 00:00:05,001"), "{}", srt);
+    }
+}
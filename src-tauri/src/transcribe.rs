@@ -0,0 +1,220 @@
+//! Audio → text: the STT and refinement steps shared by dictation, file
+//! transcription and `ektb-cli`. Nothing here touches Tauri or app state;
+//! everything is driven by an `AppConfig`.
+
+use crate::audio;
+use crate::config::AppConfig;
+use crate::encode::{self, UploadFormat};
+use crate::error::PipelineError;
+use crate::groq::{self, TranscriptSegment};
+use crate::preprocess;
+use crate::vad;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// Segments refined at once when making subtitles. Keeps a long video from
+/// firing hundreds of LLM requests together.
+const REFINE_CONCURRENCY: usize = 4;
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// The STT and LLM service behind a dictation: [`Groq`] in the app, a stub
+/// in tests.
+pub trait Provider: Send + Sync {
+    fn transcribe<'a>(
+        &'a self,
+        samples: &'a [f32],
+        sample_rate: u32,
+        config: &'a AppConfig,
+    ) -> BoxFuture<'a, Result<String, PipelineError>>;
+    fn refine<'a>(&'a self, raw_text: &'a str, config: &'a AppConfig) -> BoxFuture<'a, Result<String, PipelineError>>;
+}
+
+/// `transcribe_clip` and `refine_transcript`, with the key and models from the config.
+pub struct Groq;
+
+impl Provider for Groq {
+    fn transcribe<'a>(
+        &'a self,
+        samples: &'a [f32],
+        sample_rate: u32,
+        config: &'a AppConfig,
+    ) -> BoxFuture<'a, Result<String, PipelineError>> {
+        Box::pin(transcribe_clip(samples, sample_rate, config))
+    }
+
+    fn refine<'a>(&'a self, raw_text: &'a str, config: &'a AppConfig) -> BoxFuture<'a, Result<String, PipelineError>> {
+        Box::pin(refine_transcript(raw_text, config))
+    }
+}
+
+/// Clean up, trim silence, encode and upload one clip. Fails with `NoSpeech` if VAD
+/// or Whisper finds nothing to say.
+///
+/// Clips too large for one upload are split at silence, the chunks are
+/// transcribed in parallel and their text is joined back in order.
+pub async fn transcribe_clip(
+    samples: &[f32],
+    sample_rate: u32,
+    config: &AppConfig,
+) -> Result<String, PipelineError> {
+    // High-pass / noise suppression / AGC, as enabled in settings
+    let samples = preprocess::process(samples, sample_rate, &config.preprocess);
+
+    // Trim silence and collapse long pauses; bail out if there's no speech at all
+    let samples = vad::trim(&samples, sample_rate, &config.vad).ok_or(PipelineError::NoSpeech)?;
+
+    let parts = upload_in_chunks(&samples, sample_rate, config, false).await?;
+    let raw_text = parts
+        .iter()
+        .map(|(_, t)| t.text.trim())
+        .filter(|t| !t.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    if raw_text.is_empty() {
+        return Err(PipelineError::NoSpeech);
+    }
+    Ok(raw_text)
+}
+
+/// Like `transcribe_clip`, but keeps Whisper's segment timing, in seconds
+/// from the start of `samples`. Silence is left in place, since trimming it
+/// would shift every timestamp after it.
+pub async fn transcribe_clip_timed(
+    samples: &[f32],
+    sample_rate: u32,
+    config: &AppConfig,
+) -> Result<Vec<TranscriptSegment>, PipelineError> {
+    let samples = preprocess::process(samples, sample_rate, &config.preprocess);
+    if !vad::detect(&samples, sample_rate, &config.vad).has_speech(&config.vad) {
+        return Err(PipelineError::NoSpeech);
+    }
+
+    let parts = upload_in_chunks(&samples, sample_rate, config, true).await?;
+    let segments: Vec<TranscriptSegment> = parts
+        .into_iter()
+        .flat_map(|(offset, t)| {
+            t.segments.into_iter().map(move |s| TranscriptSegment {
+                start: s.start + offset,
+                end: s.end + offset,
+                text: s.text.trim().to_string(),
+            })
+        })
+        .filter(|s| !s.text.is_empty())
+        .collect();
+    if segments.is_empty() {
+        return Err(PipelineError::NoSpeech);
+    }
+    Ok(segments)
+}
+
+/// Upload `samples` in chunks under the provider limit, split at silence and
+/// transcribed in parallel. Returns each chunk's transcription in order,
+/// with the chunk's start time in seconds.
+async fn upload_in_chunks(
+    samples: &[f32],
+    sample_rate: u32,
+    config: &AppConfig,
+    timed: bool,
+) -> Result<Vec<(f64, groq::Transcription)>, PipelineError> {
+    if config.groq_api_key.is_empty() {
+        return Err(PipelineError::NoApiKey);
+    }
+
+    // 10% headroom under the provider limit
+    let max_secs = groq::MAX_UPLOAD_BYTES * 9 / 10 / audio::WAV_BYTES_PER_SEC;
+    let chunks = vad::split_at_silence(samples, sample_rate, max_secs * sample_rate as usize);
+    if chunks.len() > 1 {
+        log::info!("Long recording: uploading {} chunks in parallel", chunks.len());
+    }
+
+    // Dropping the set (e.g. on cancel) aborts every outstanding upload
+    let mut uploads = tokio::task::JoinSet::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let clip = samples[chunk.clone()].to_vec();
+        let api_key = config.groq_api_key.clone();
+        let model = config.stt_model.clone();
+        let format = UploadFormat::from_name(&config.upload_format);
+        uploads.spawn(async move { (i, upload_clip(clip, sample_rate, format, api_key, model, timed).await) });
+    }
+
+    let mut parts: Vec<Option<groq::Transcription>> = chunks.iter().map(|_| None).collect();
+    while let Some(joined) = uploads.join_next().await {
+        let (i, transcription) = joined.map_err(|e| PipelineError::Provider { status: 0, body: e.to_string() })?;
+        parts[i] = Some(transcription?);
+    }
+
+    Ok(chunks
+        .iter()
+        .zip(parts)
+        .filter_map(|(chunk, part)| Some((chunk.start as f64 / sample_rate as f64, part?)))
+        .collect())
+}
+
+async fn upload_clip(
+    samples: Vec<f32>,
+    sample_rate: u32,
+    format: UploadFormat,
+    api_key: String,
+    model: String,
+    timed: bool,
+) -> Result<groq::Transcription, PipelineError> {
+    let started = std::time::Instant::now();
+    let bytes = encode::encode(&samples, sample_rate, format)
+        .map_err(|e| PipelineError::Encoding(e.to_string()))?;
+    log::info!(
+        "Encoded {:.1}s as {:?}: {} KB in {} ms",
+        samples.len() as f32 / sample_rate as f32,
+        format,
+        bytes.len() / 1024,
+        started.elapsed().as_millis()
+    );
+
+    groq::transcribe_audio(bytes, format, &api_key, &model, timed)
+        .await
+        .map_err(PipelineError::from_provider)
+}
+
+pub async fn refine_transcript(raw_text: &str, config: &AppConfig) -> Result<String, PipelineError> {
+    let vocab: Vec<(String, String)> = config
+        .vocabulary
+        .iter()
+        .map(|v| (v.from.clone(), v.to.clone()))
+        .collect();
+
+    groq::refine_text(raw_text, &config.groq_api_key, &config.system_prompt, &config.llm_model, &vocab)
+        .await
+        .map_err(PipelineError::from_provider)
+}
+
+/// Refine each segment on its own so it keeps its timing. A segment whose
+/// refinement fails with a retryable error keeps its raw text rather than
+/// sinking a long file; other errors (e.g. a rejected key) fail the whole run.
+pub async fn refine_segments(
+    segments: &[TranscriptSegment],
+    config: &AppConfig,
+) -> Result<Vec<TranscriptSegment>, PipelineError> {
+    let config = Arc::new(config.clone());
+    let permits = Arc::new(tokio::sync::Semaphore::new(REFINE_CONCURRENCY));
+    let mut tasks = tokio::task::JoinSet::new();
+    for (i, segment) in segments.iter().enumerate() {
+        let (config, permits, text) = (Arc::clone(&config), Arc::clone(&permits), segment.text.clone());
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            (i, refine_transcript(&text, &config).await)
+        });
+    }
+
+    let mut refined = segments.to_vec();
+    while let Some(joined) = tasks.join_next().await {
+        let (i, result) = joined.map_err(|e| PipelineError::Provider { status: 0, body: e.to_string() })?;
+        match result {
+            Ok(text) if !text.trim().is_empty() => refined[i].text = text.trim().to_string(),
+            Ok(_) => {}
+            Err(e) if e.retryable() => log::warn!("Segment {} kept unrefined: {}", i, e),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(refined)
+}
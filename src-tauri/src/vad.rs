@@ -0,0 +1,310 @@
+//! Voice activity detection: trim silence before upload.
+//!
+//! A frame-based energy + zero-crossing detector. Low-energy, high-ZCR
+//! frames are kept so fricatives ("س", "ش", "ف") at word edges survive.
+//! Leading/trailing silence is cut down to a small padding, and long
+//! pauses in the middle are collapsed, which both shortens the upload and
+//! stops Whisper from hallucinating text into silence. In continuous mode
+//! the same detector finds the pauses where the stream is cut into segments.
+
+use crate::config::VadConfig;
+
+/// Analysis frame length.
+const FRAME_MS: u32 = 30;
+
+/// Speech segment in samples, `start..end`.
+pub type Segment = std::ops::Range<usize>;
+
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    rms: f32,
+    zcr: f32,
+}
+
+/// Outcome of running VAD over a clip.
+pub struct VadResult {
+    /// Speech segments (padded), in samples of the input.
+    pub segments: Vec<Segment>,
+    /// Milliseconds of raw (unpadded) speech detected.
+    pub speech_ms: u32,
+    /// Estimated background level (frame RMS), for diagnostics.
+    pub noise_floor: f32,
+}
+
+impl VadResult {
+    pub fn has_speech(&self, cfg: &VadConfig) -> bool {
+        !self.segments.is_empty() && self.speech_ms >= cfg.min_speech_ms
+    }
+}
+
+/// Find the speech segments in `samples`.
+pub fn detect(samples: &[f32], sample_rate: u32, cfg: &VadConfig) -> VadResult {
+    let frame_len = (sample_rate * FRAME_MS / 1000).max(1) as usize;
+    let frames: Vec<Frame> = samples.chunks(frame_len).map(analyze).collect();
+    if frames.is_empty() {
+        return VadResult { segments: Vec::new(), speech_ms: 0, noise_floor: 0.0 };
+    }
+
+    let (noise_floor, loud) = levels(&frames);
+    // Well above the background, but never so high that a clip which is all
+    // speech (no silence to measure) loses its quieter syllables
+    let adaptive = (noise_floor * cfg.noise_multiplier).min(loud * 0.25);
+    let threshold = cfg.energy_threshold.max(adaptive);
+
+    let raw: Vec<bool> = frames
+        .iter()
+        .map(|f| f.rms >= threshold || (f.rms >= threshold * 0.5 && f.zcr >= cfg.zcr_threshold))
+        .collect();
+    let speech_frames = raw.iter().filter(|&&s| s).count();
+
+    // Pad every speech frame on both sides so word onsets/tails aren't clipped
+    let pad = (cfg.padding_ms / FRAME_MS) as usize;
+    let mut padded = vec![false; raw.len()];
+    for (i, _) in raw.iter().enumerate().filter(|(_, &s)| s) {
+        let lo = i.saturating_sub(pad);
+        let hi = (i + pad + 1).min(raw.len());
+        padded[lo..hi].iter_mut().for_each(|p| *p = true);
+    }
+
+    let mut segments = Vec::new();
+    let mut start = None;
+    for (i, &speech) in padded.iter().chain(std::iter::once(&false)).enumerate() {
+        match (speech, start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                segments.push(s * frame_len..(i * frame_len).min(samples.len()));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    VadResult {
+        segments,
+        speech_ms: speech_frames as u32 * FRAME_MS,
+        noise_floor,
+    }
+}
+
+/// Run VAD and return the audio to upload, or `None` if there is no speech.
+///
+/// With VAD disabled this falls back to a single whole-clip RMS check
+/// against `energy_threshold` and returns the clip untouched.
+pub fn trim(samples: &[f32], sample_rate: u32, cfg: &VadConfig) -> Option<Vec<f32>> {
+    if !cfg.enabled {
+        let rms = rms(samples);
+        log::info!("Audio RMS energy: {:.6}", rms);
+        return (rms >= cfg.energy_threshold).then(|| samples.to_vec());
+    }
+
+    let result = detect(samples, sample_rate, cfg);
+    log::info!(
+        "VAD: {} segment(s), {} ms speech, noise floor {:.5}",
+        result.segments.len(),
+        result.speech_ms,
+        result.noise_floor
+    );
+    if !result.has_speech(cfg) {
+        return None;
+    }
+
+    let max_pause = (sample_rate * cfg.max_pause_ms / 1000) as usize;
+    let mut out = Vec::with_capacity(samples.len());
+    let mut prev_end: Option<usize> = None;
+    for seg in &result.segments {
+        // Keep at most `max_pause` of each gap between segments
+        if let Some(end) = prev_end {
+            let gap_end = seg.start.min(end + max_pause);
+            out.extend_from_slice(&samples[end..gap_end]);
+        }
+        out.extend_from_slice(&samples[seg.clone()]);
+        prev_end = Some(seg.end);
+    }
+
+    log::info!(
+        "VAD trimmed {:.2}s → {:.2}s",
+        samples.len() as f32 / sample_rate as f32,
+        out.len() as f32 / sample_rate as f32
+    );
+    Some(out)
+}
+
+/// A cut point in a growing stream, found by [`next_cut`].
+pub struct Cut {
+    /// Samples from the start of the analysed slice up to the cut.
+    pub end: usize,
+    /// Whether the audio before the cut contains speech.
+    pub speech: bool,
+}
+
+/// Find the first natural pause in a still-growing stream: a speech segment
+/// followed by at least `segment_pause_ms` of silence. Leading silence is
+/// reported as a cut without speech so the caller can drop it. `None` means
+/// the speaker hasn't paused yet — wait for more audio.
+pub fn next_cut(samples: &[f32], sample_rate: u32, cfg: &VadConfig) -> Option<Cut> {
+    let pause = (sample_rate * cfg.segment_pause_ms / 1000).max(1) as usize;
+    let result = detect(samples, sample_rate, cfg);
+    match result.segments.first() {
+        // Keep one pause of lead-in so a word starting right now isn't clipped
+        None => (samples.len() > 2 * pause).then(|| Cut { end: samples.len() - pause, speech: false }),
+        Some(first) => {
+            let next = result.segments.get(1).map_or(samples.len(), |s| s.start);
+            (next - first.end >= pause).then_some(Cut { end: first.end, speech: true })
+        }
+    }
+}
+
+/// Split `samples` into consecutive chunks of at most `max_len` samples.
+/// Each cut lands on the quietest frame in the second half of its window,
+/// so long recordings are split between words rather than through them.
+pub fn split_at_silence(samples: &[f32], sample_rate: u32, max_len: usize) -> Vec<Segment> {
+    let frame_len = (sample_rate * FRAME_MS / 1000).max(1) as usize;
+    let max_len = max_len.max(frame_len * 2);
+
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while samples.len() - start > max_len {
+        let window = start + max_len / 2..start + max_len - frame_len;
+        let cut = window
+            .step_by(frame_len)
+            .min_by(|&a, &b| rms(&samples[a..a + frame_len]).total_cmp(&rms(&samples[b..b + frame_len])))
+            .map_or(start + max_len, |quietest| quietest + frame_len / 2);
+        chunks.push(start..cut);
+        start = cut;
+    }
+    chunks.push(start..samples.len());
+    chunks
+}
+
+pub fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+fn analyze(frame: &[f32]) -> Frame {
+    let crossings = frame
+        .windows(2)
+        .filter(|w| (w[0] >= 0.0) != (w[1] >= 0.0))
+        .count();
+    Frame {
+        rms: rms(frame),
+        zcr: crossings as f32 / frame.len().max(1) as f32,
+    }
+}
+
+/// 10th- and 90th-percentile frame energy: the background level (robust to
+/// a few dead-silent frames at the start of the stream) and the loud level.
+fn levels(frames: &[Frame]) -> (f32, f32) {
+    let mut levels: Vec<f32> = frames.iter().map(|f| f.rms).collect();
+    levels.sort_by(|a, b| a.total_cmp(b));
+    (levels[levels.len() / 10], levels[levels.len() * 9 / 10])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16_000;
+    const FRAME: usize = (RATE * FRAME_MS / 1000) as usize;
+
+    fn tone(hz: f32, amplitude: f32, secs: f32) -> Vec<f32> {
+        let len = (RATE as f32 * secs) as usize;
+        (0..len)
+            .map(|i| amplitude * (2.0 * std::f32::consts::PI * hz * i as f32 / RATE as f32).sin())
+            .collect()
+    }
+
+    fn silence(secs: f32) -> Vec<f32> {
+        vec![0.0; (RATE as f32 * secs) as usize]
+    }
+
+    fn secs(s: f32) -> usize {
+        (RATE as f32 * s) as usize
+    }
+
+    #[test]
+    fn finds_tone_between_silences() {
+        let cfg = VadConfig::default();
+        let clip = [silence(1.0), tone(440.0, 0.3, 1.0), silence(1.0)].concat();
+
+        let result = detect(&clip, RATE, &cfg);
+        assert_eq!(result.segments.len(), 1);
+        let seg = &result.segments[0];
+        let pad = secs(cfg.padding_ms as f32 / 1000.0);
+        assert!(seg.start.abs_diff(secs(1.0) - pad) <= FRAME, "starts at {}", seg.start);
+        assert!(seg.end.abs_diff(secs(2.0) + pad) <= FRAME, "ends at {}", seg.end);
+        assert!(result.speech_ms.abs_diff(1000) <= FRAME_MS);
+    }
+
+    #[test]
+    fn trim_drops_silence_only_clip() {
+        let cfg = VadConfig::default();
+        assert!(trim(&silence(2.0), RATE, &cfg).is_none());
+    }
+
+    #[test]
+    fn trim_collapses_long_pause() {
+        let cfg = VadConfig::default();
+        let clip = [tone(440.0, 0.3, 0.5), silence(3.0), tone(440.0, 0.3, 0.5)].concat();
+
+        let trimmed = trim(&clip, RATE, &cfg).unwrap();
+        // Both bursts, padding around them and at most `max_pause_ms` between
+        let max = secs(1.0 + (2 * cfg.padding_ms + cfg.max_pause_ms) as f32 / 1000.0) + 2 * FRAME;
+        assert!(trimmed.len() < max, "{} samples left", trimmed.len());
+        assert!(trimmed.len() >= secs(1.0));
+    }
+
+    #[test]
+    fn quiet_high_zcr_frames_count_as_speech() {
+        let cfg = VadConfig::default();
+        // RMS between half the energy threshold and the threshold itself
+        let amplitude = cfg.energy_threshold;
+        let fricative = [silence(0.5), tone(6_000.0, amplitude, 0.3), silence(1.0)].concat();
+        let hum = [silence(0.5), tone(200.0, amplitude, 0.3), silence(1.0)].concat();
+
+        assert!(detect(&fricative, RATE, &cfg).has_speech(&cfg));
+        assert!(!detect(&hum, RATE, &cfg).has_speech(&cfg));
+    }
+
+    #[test]
+    fn next_cut_waits_for_a_pause() {
+        let cfg = VadConfig::default();
+        let talking = [silence(0.2), tone(440.0, 0.3, 1.0)].concat();
+        assert!(next_cut(&talking, RATE, &cfg).is_none());
+
+        let paused = [talking, silence(1.0)].concat();
+        let cut = next_cut(&paused, RATE, &cfg).unwrap();
+        assert!(cut.speech);
+        let pad = secs(cfg.padding_ms as f32 / 1000.0);
+        assert!(cut.end.abs_diff(secs(1.2) + pad) <= FRAME, "cut at {}", cut.end);
+    }
+
+    #[test]
+    fn split_lands_in_the_gaps() {
+        // Three 0.7 s bursts with 0.2 s gaps; chunks of at most 1 s
+        let burst = tone(440.0, 0.3, 0.7);
+        let gap = silence(0.2);
+        let clip = [burst.clone(), gap.clone(), burst.clone(), gap, burst].concat();
+        let max_len = secs(1.0);
+
+        let chunks = split_at_silence(&clip, RATE, max_len);
+        assert!(chunks.len() >= 3);
+        assert_eq!(chunks[0].start, 0);
+        assert_eq!(chunks.last().unwrap().end, clip.len());
+        for pair in chunks.windows(2) {
+            assert_eq!(pair[0].end, pair[1].start);
+            let cut = pair[0].end;
+            let around = &clip[cut - FRAME / 2..cut + FRAME / 2];
+            assert!(around.iter().all(|&s| s == 0.0), "cut at {} is not silent", cut);
+        }
+        assert!(chunks.iter().all(|c| c.len() <= max_len));
+    }
+
+    #[test]
+    fn split_keeps_short_clip_whole() {
+        let clip = tone(440.0, 0.3, 0.5);
+        assert_eq!(split_at_silence(&clip, RATE, secs(1.0)), vec![0..clip.len()]);
+    }
+}
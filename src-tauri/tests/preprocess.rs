@@ -0,0 +1,133 @@
+//! Before/after checks for the preprocessing chain on the bundled fixtures.
+//!
+//! `quiet_speech.wav`: a distant, quiet speaker (peaks around -34 dBFS).
+//! `noisy_speech.wav`: normal-level speech over broadband noise and 50 Hz
+//! mains hum. Both are 4 s at 16 kHz with a 0.5 s pause every 2 s.
+
+use app_lib::preprocess::{denoise, high_pass, normalize};
+
+const RATE: u32 = 16_000;
+
+fn fixture(name: &str) -> Vec<f32> {
+    let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+    let mut reader = hound::WavReader::open(path).unwrap();
+    assert_eq!(reader.spec().sample_rate, RATE);
+    reader
+        .samples::<i16>()
+        .map(|s| s.unwrap() as f32 / i16::MAX as f32)
+        .collect()
+}
+
+fn rms(samples: &[f32]) -> f32 {
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+fn db(ratio: f32) -> f32 {
+    20.0 * ratio.log10()
+}
+
+/// Magnitude of one frequency component (Goertzel).
+fn tone_level(samples: &[f32], hz: f32) -> f32 {
+    let w = 2.0 * std::f32::consts::PI * hz / RATE as f32;
+    let coeff = 2.0 * w.cos();
+    let (mut s1, mut s2) = (0.0f32, 0.0f32);
+    for &x in samples {
+        let s0 = x + coeff * s1 - s2;
+        s2 = s1;
+        s1 = s0;
+    }
+    (s1 * s1 + s2 * s2 - coeff * s1 * s2).sqrt() / samples.len() as f32
+}
+
+/// 1.6–1.9 s: inside the first pause, where only noise is left.
+fn pause(samples: &[f32]) -> &[f32] {
+    &samples[RATE as usize * 16 / 10..RATE as usize * 19 / 10]
+}
+
+/// 0.2–1.4 s: inside the first stretch of speech.
+fn speech(samples: &[f32]) -> &[f32] {
+    &samples[RATE as usize / 5..RATE as usize * 14 / 10]
+}
+
+#[test]
+fn high_pass_removes_mains_hum() {
+    let before = fixture("noisy_speech.wav");
+    let mut after = before.clone();
+    high_pass(&mut after, RATE, 80.0);
+
+    // Skip the filter's settling time
+    let hum_drop =
+        db(tone_level(&after[RATE as usize..], 50.0) / tone_level(&before[RATE as usize..], 50.0));
+    assert!(hum_drop < -6.0, "50 Hz hum only dropped {:.1} dB", hum_drop);
+}
+
+#[test]
+fn high_pass_keeps_the_voice() {
+    let before = fixture("quiet_speech.wav");
+    let mut after = before.clone();
+    high_pass(&mut after, RATE, 80.0);
+
+    let change = db(rms(speech(&after)) / rms(speech(&before)));
+    assert!(change > -1.0, "speech lost {:.1} dB", change);
+}
+
+#[test]
+fn denoise_quiets_pauses_and_keeps_speech() {
+    let mut before = fixture("noisy_speech.wav");
+    high_pass(&mut before, RATE, 80.0);
+    let after = denoise(&before, RATE);
+    assert_eq!(after.len(), before.len());
+
+    let noise_drop = db(rms(pause(&after)) / rms(pause(&before)));
+    assert!(
+        noise_drop < -10.0,
+        "noise in pauses only dropped {:.1} dB",
+        noise_drop
+    );
+
+    // Speech-to-pause contrast must improve
+    let contrast_before = db(rms(speech(&before)) / rms(pause(&before)));
+    let contrast_after = db(rms(speech(&after)) / rms(pause(&after)));
+    assert!(
+        contrast_after > contrast_before + 6.0,
+        "contrast {:.1} dB → {:.1} dB",
+        contrast_before,
+        contrast_after
+    );
+}
+
+#[test]
+fn agc_lifts_quiet_speaker_to_target() {
+    let before = fixture("quiet_speech.wav");
+    let mut after = before.clone();
+    normalize(&mut after, RATE, -20.0, 30.0);
+
+    let gain = db(rms(speech(&after)) / rms(speech(&before)));
+    assert!(gain > 10.0, "gain only {:.1} dB", gain);
+    let peak = after.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+    assert!(peak <= 0.99, "clipped: peak {}", peak);
+}
+
+#[test]
+fn agc_gain_is_capped() {
+    let before = fixture("quiet_speech.wav");
+    let mut after = before.clone();
+    normalize(&mut after, RATE, -20.0, 6.0);
+
+    let gain = db(rms(&after) / rms(&before));
+    assert!(
+        (gain - 6.0).abs() < 0.1,
+        "gain {:.2} dB, cap was 6 dB",
+        gain
+    );
+}
+
+#[test]
+fn agc_never_clips_loud_input() {
+    let before = fixture("noisy_speech.wav");
+    let mut after = before.clone();
+    normalize(&mut after, RATE, -3.0, 30.0);
+
+    let peak = after.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+    assert!(peak <= 0.99 + 1e-6, "clipped: peak {}", peak);
+}